@@ -1,29 +1,202 @@
+use std::fmt;
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use bon::Builder;
+use moonshine_save::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::screens::prelude::*;
 
 pub mod prelude {
-    pub use super::VolumeSettings;
     pub use super::my_audio_plugin;
+    pub use super::{DuckMusic, Music, Sfx, Ui, VolumeKind, VolumeSettings};
     pub use bevy_kira_audio::prelude::*;
 }
 
-#[derive(Component, Resource, Debug, Builder)]
+/// Volume settings of the player. The saved component is edited by the options screen
+/// and copied into the resource, which is applied to the volume of every audio channel.
+#[derive(Component, Resource, Reflect, Debug, Clone, PartialEq, Builder)]
+#[reflect(Component, Default)]
+#[require(Save, Unload)]
 pub struct VolumeSettings {
+    master: f64,
     sfx: f64,
     music: f64,
 }
 
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        VolumeSettings::builder()
+            .master(1.0)
+            .sfx(1.0)
+            .music(1.0)
+            .build()
+    }
+}
+
 impl VolumeSettings {
-    pub fn calc_sfx(&self, value: f64) -> f64 {
-        self.sfx * value
+    pub fn sfx_volume(&self) -> f64 {
+        self.master * self.sfx
     }
-    pub fn calc_music(&self, value: f64) -> f64 {
-        self.music * value
+    pub fn music_volume(&self) -> f64 {
+        self.master * self.music
+    }
+    pub fn get(&self, kind: VolumeKind) -> f64 {
+        match kind {
+            VolumeKind::Master => self.master,
+            VolumeKind::Music => self.music,
+            VolumeKind::Sfx => self.sfx,
+        }
+    }
+    /// Sets the volume of `kind`, clamped to `0.0..=1.0`.
+    pub fn set(&mut self, kind: VolumeKind, value: f64) {
+        let value = value.clamp(0.0, 1.0);
+        match kind {
+            VolumeKind::Master => self.master = value,
+            VolumeKind::Music => self.music = value,
+            VolumeKind::Sfx => self.sfx = value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumIter)]
+pub enum VolumeKind {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl fmt::Display for VolumeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VolumeKind::Master => "master",
+            VolumeKind::Music => "music",
+            VolumeKind::Sfx => "sfx",
+        };
+        f.pad(name)
     }
 }
 
+/// Audio channel for background music.
+#[derive(Resource)]
+pub struct Music;
+
+/// Audio channel for gameplay sound effects.
+#[derive(Resource)]
+pub struct Sfx;
+
+/// Audio channel for menu sounds, which keep playing while the game is paused.
+#[derive(Resource)]
+pub struct Ui;
+
 pub fn my_audio_plugin(app: &mut App) {
     app.add_plugins(AudioPlugin)
-        .insert_resource(VolumeSettings::builder().sfx(1.0).music(1.0).build());
+        .add_audio_channel::<Music>()
+        .add_audio_channel::<Sfx>()
+        .add_audio_channel::<Ui>()
+        .register_type::<VolumeSettings>()
+        .init_resource::<VolumeSettings>()
+        .init_resource::<MusicDucking>()
+        .add_event::<DuckMusic>()
+        // the save is loaded on entering `SplashFirst`, older saves come without settings
+        .add_systems(OnExit(GameScreen::SplashFirst), ensure_volume_settings)
+        .add_systems(
+            Update,
+            (
+                sync_volume_settings,
+                apply_effects_volume.run_if(resource_changed::<VolumeSettings>),
+                duck_music,
+            )
+                .chain(),
+        );
+}
+
+fn ensure_volume_settings(mut commands: Commands, mut settings: Query<&mut VolumeSettings>) {
+    if settings.is_empty() {
+        tracing::info!("no volume settings in save, using the defaults");
+        commands.spawn(VolumeSettings::default());
+    }
+    // saves can be edited by hand, `set` clamps what was loaded
+    for mut settings in settings.iter_mut() {
+        for kind in VolumeKind::iter() {
+            let value = settings.get(kind);
+            settings.set(kind, value);
+        }
+    }
+}
+
+fn sync_volume_settings(
+    settings: Single<&VolumeSettings, Changed<VolumeSettings>>,
+    mut volume: ResMut<VolumeSettings>,
+) {
+    *volume = settings.clone();
+}
+
+fn apply_effects_volume(
+    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
+    ui: Res<AudioChannel<Ui>>,
+) {
+    sfx.set_volume(volume.sfx_volume());
+    ui.set_volume(volume.sfx_volume());
+}
+
+/// Lowers the music for a moment during loud moments, like the player dying or dashing.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DuckMusic {
+    /// Fraction of the music volume kept while ducked.
+    pub volume: f64,
+    pub duration: Duration,
+}
+
+#[derive(Resource, Debug)]
+struct MusicDucking {
+    volume: f64,
+    timer: Timer,
+    /// Music channel volume last sent to kira.
+    applied: Option<f64>,
+}
+
+impl Default for MusicDucking {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            timer: Timer::new(Duration::ZERO, TimerMode::Once),
+            applied: None,
+        }
+    }
+}
+
+fn duck_music(
+    mut events: EventReader<DuckMusic>,
+    mut ducking: ResMut<MusicDucking>,
+    volume: Res<VolumeSettings>,
+    music: Res<AudioChannel<Music>>,
+    time: Res<Time>,
+) {
+    for event in events.read() {
+        // a stronger duck takes over, a weaker one only extends the current one
+        if ducking.timer.finished() || event.volume < ducking.volume {
+            ducking.volume = event.volume;
+        }
+        let remaining = ducking.timer.remaining().max(event.duration);
+        ducking.timer = Timer::new(remaining, TimerMode::Once);
+    }
+    ducking.timer.tick(time.delta());
+
+    let (target, fade) = if ducking.timer.finished() {
+        (volume.music_volume(), Duration::from_millis(500))
+    } else {
+        (
+            volume.music_volume() * ducking.volume,
+            Duration::from_millis(80),
+        )
+    };
+    if ducking.applied == Some(target) {
+        return;
+    }
+    ducking.applied = Some(target);
+    music.set_volume(target).fade_in(AudioTween::linear(fade));
 }
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use moonshine_save::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::bindings_plugin;
+    pub use super::{ActionBinding, BindableAction, Binding, BindingSlot, InputBindings};
+}
+
+pub fn bindings_plugin(app: &mut App) {
+    app.register_type::<InputBindings>()
+        .register_type::<ActionBinding>()
+        .register_type::<Binding>()
+        // the save is loaded on entering `SplashFirst`, older saves come without bindings
+        .add_systems(OnExit(GameScreen::SplashFirst), ensure_input_bindings);
+}
+
+/// Actions whose buttons can be changed from the controls screen.
+///
+/// Moving and aiming stay on the sticks, WASD, the dpad and the mouse, and menus keep
+/// their own fixed navigation so a bad binding can always be undone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumIter)]
+pub enum BindableAction {
+    Shoot,
+    Dash,
+    Recall,
+    Nova,
+    Confirm,
+    Pause,
+}
+
+impl fmt::Display for BindableAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BindableAction::Shoot => "shoot",
+            BindableAction::Dash => "dash",
+            BindableAction::Recall => "recall",
+            BindableAction::Nova => "blood nova",
+            BindableAction::Confirm => "confirm",
+            BindableAction::Pause => "pause",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Every action has one binding per input device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumIter)]
+pub enum BindingSlot {
+    KeyboardMouse,
+    Gamepad,
+}
+
+impl BindingSlot {
+    pub fn accepts(&self, binding: Binding) -> bool {
+        match self {
+            BindingSlot::KeyboardMouse => matches!(binding, Binding::Key(_) | Binding::Mouse(_)),
+            BindingSlot::Gamepad => matches!(binding, Binding::Gamepad(_)),
+        }
+    }
+}
+
+impl fmt::Display for BindingSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BindingSlot::KeyboardMouse => "keys",
+            BindingSlot::Gamepad => "pad",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    fn insert_into<A: Actionlike>(self, input_map: &mut InputMap<A>, action: A) {
+        match self {
+            Binding::Key(key) => input_map.insert(action, key),
+            Binding::Mouse(button) => input_map.insert(action, button),
+            Binding::Gamepad(button) => input_map.insert(action, button),
+        };
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                f.write_str(&name.to_lowercase())
+            }
+            Binding::Mouse(MouseButton::Left) => f.write_str("left click"),
+            Binding::Mouse(MouseButton::Right) => f.write_str("right click"),
+            Binding::Mouse(MouseButton::Middle) => f.write_str("middle click"),
+            Binding::Mouse(button) => write!(f, "mouse {}", format!("{button:?}").to_lowercase()),
+            Binding::Gamepad(button) => {
+                let name = match button {
+                    GamepadButton::LeftTrigger => "left bumper",
+                    GamepadButton::RightTrigger => "right bumper",
+                    GamepadButton::LeftTrigger2 => "left trigger",
+                    GamepadButton::RightTrigger2 => "right trigger",
+                    GamepadButton::LeftThumb => "L3",
+                    GamepadButton::RightThumb => "R3",
+                    GamepadButton::South => "south button",
+                    GamepadButton::East => "east button",
+                    GamepadButton::North => "north button",
+                    GamepadButton::West => "west button",
+                    GamepadButton::DPadUp => "dpad up",
+                    GamepadButton::DPadDown => "dpad down",
+                    GamepadButton::DPadLeft => "dpad left",
+                    GamepadButton::DPadRight => "dpad right",
+                    other => return f.write_str(&format!("{other:?}").to_lowercase()),
+                };
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct ActionBinding {
+    pub keyboard: Binding,
+    pub gamepad: Binding,
+}
+
+impl ActionBinding {
+    pub fn get(&self, slot: BindingSlot) -> Binding {
+        match slot {
+            BindingSlot::KeyboardMouse => self.keyboard,
+            BindingSlot::Gamepad => self.gamepad,
+        }
+    }
+
+    fn get_mut(&mut self, slot: BindingSlot) -> &mut Binding {
+        match slot {
+            BindingSlot::KeyboardMouse => &mut self.keyboard,
+            BindingSlot::Gamepad => &mut self.gamepad,
+        }
+    }
+}
+
+/// The player's buttons for every [`BindableAction`], kept in the save file.
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component, Default)]
+#[require(Save, Unload)]
+pub struct InputBindings {
+    pub shoot: ActionBinding,
+    pub dash: ActionBinding,
+    // saves from before the ability existed come without a binding for it
+    #[reflect(default = "default_recall_binding")]
+    pub recall: ActionBinding,
+    #[reflect(default = "default_nova_binding")]
+    pub nova: ActionBinding,
+    pub confirm: ActionBinding,
+    pub pause: ActionBinding,
+}
+
+fn default_recall_binding() -> ActionBinding {
+    InputBindings::default().recall
+}
+
+fn default_nova_binding() -> ActionBinding {
+    InputBindings::default().nova
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            shoot: ActionBinding {
+                keyboard: Binding::Mouse(MouseButton::Left),
+                gamepad: Binding::Gamepad(GamepadButton::RightTrigger),
+            },
+            dash: ActionBinding {
+                keyboard: Binding::Key(KeyCode::Space),
+                gamepad: Binding::Gamepad(GamepadButton::LeftTrigger2),
+            },
+            recall: ActionBinding {
+                keyboard: Binding::Mouse(MouseButton::Right),
+                gamepad: Binding::Gamepad(GamepadButton::LeftTrigger),
+            },
+            nova: ActionBinding {
+                keyboard: Binding::Key(KeyCode::KeyE),
+                gamepad: Binding::Gamepad(GamepadButton::West),
+            },
+            confirm: ActionBinding {
+                keyboard: Binding::Key(KeyCode::Enter),
+                gamepad: Binding::Gamepad(GamepadButton::RightThumb),
+            },
+            pause: ActionBinding {
+                keyboard: Binding::Key(KeyCode::Escape),
+                gamepad: Binding::Gamepad(GamepadButton::Start),
+            },
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: BindableAction) -> &ActionBinding {
+        match action {
+            BindableAction::Shoot => &self.shoot,
+            BindableAction::Dash => &self.dash,
+            BindableAction::Recall => &self.recall,
+            BindableAction::Nova => &self.nova,
+            BindableAction::Confirm => &self.confirm,
+            BindableAction::Pause => &self.pause,
+        }
+    }
+
+    fn get_mut(&mut self, action: BindableAction) -> &mut ActionBinding {
+        match action {
+            BindableAction::Shoot => &mut self.shoot,
+            BindableAction::Dash => &mut self.dash,
+            BindableAction::Recall => &mut self.recall,
+            BindableAction::Nova => &mut self.nova,
+            BindableAction::Confirm => &mut self.confirm,
+            BindableAction::Pause => &mut self.pause,
+        }
+    }
+
+    /// Binds `binding` to `action`. The tutorial runs gameplay and screen actions at
+    /// the same time, so a button may only belong to one action: if another action
+    /// already uses it, that action takes over the previous binding of `action`.
+    ///
+    /// Returns the action that was swapped, if any.
+    pub fn rebind(
+        &mut self,
+        action: BindableAction,
+        slot: BindingSlot,
+        binding: Binding,
+    ) -> Option<BindableAction> {
+        let previous = self.get(action).get(slot);
+        let conflict = BindableAction::iter()
+            .find(|other| *other != action && self.get(*other).get(slot) == binding);
+        if let Some(other) = conflict {
+            *self.get_mut(other).get_mut(slot) = previous;
+        }
+        *self.get_mut(action).get_mut(slot) = binding;
+        conflict
+    }
+
+    /// Adds both bindings of `bindable` to `input_map` under `action`.
+    pub fn insert_into<A: Actionlike>(
+        &self,
+        input_map: &mut InputMap<A>,
+        bindable: BindableAction,
+        action: A,
+    ) {
+        let binding = self.get(bindable);
+        binding.keyboard.insert_into(input_map, action.clone());
+        binding.gamepad.insert_into(input_map, action);
+    }
+
+    pub fn input_map<A: Actionlike>(&self, bindable: BindableAction, action: A) -> InputMap<A> {
+        let mut input_map = InputMap::default();
+        self.insert_into(&mut input_map, bindable, action);
+        input_map
+    }
+
+    /// Both bindings of `action` for display, e.g. `space OR left trigger`.
+    pub fn describe(&self, action: BindableAction) -> String {
+        let binding = self.get(action);
+        format!("{} OR {}", binding.keyboard, binding.gamepad)
+    }
+}
+
+fn ensure_input_bindings(mut commands: Commands, bindings: Query<(), With<InputBindings>>) {
+    if bindings.is_empty() {
+        tracing::info!("no input bindings in save, using the defaults");
+        commands.spawn(InputBindings::default());
+    }
+}
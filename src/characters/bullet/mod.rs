@@ -5,9 +5,11 @@ use bevy::prelude::*;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+pub mod patterns;
+
 pub fn bullet_plugin(app: &mut App) {
     app.add_systems(
-        Update,
+        FixedUpdate,
         (
             apply_bullet_velocity,
             bullet_lifetime_tick,
@@ -17,7 +19,7 @@ pub fn bullet_plugin(app: &mut App) {
             .run_if(not(in_state(GameScreen::SplashFirst))),
     )
     .add_systems(
-        PostUpdate,
+        FixedPostUpdate,
         (bullet_lifetime_tick, bullets_despawn_from_wraps)
             .run_if(not(in_state(GameScreen::SplashFirst))),
     );
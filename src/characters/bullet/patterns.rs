@@ -0,0 +1,311 @@
+use std::f32::consts::{PI, TAU};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::characters::bullet::{BulletMaxWrap, bullet_base};
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::Player;
+use crate::characters::{AimDirRotationOffset, prelude::*};
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::patterns_plugin;
+    pub use super::{BulletPattern, EnemyBullet, FirePattern, Homing, PatternShape};
+}
+
+pub fn patterns_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (fire_patterns, home_bullets).run_if(not(in_state(GameScreen::SplashFirst))),
+    );
+}
+
+/// How the bullets of a [`BulletPattern`] are laid out. Angles are in degrees and times in
+/// seconds.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PatternShape {
+    /// `count` bullets at once, `spread` apart and centered on the aim.
+    Fan { count: usize, spread: f32 },
+    /// `count` bullets at once, evenly around the shooter starting at the aim.
+    Ring { count: usize },
+    /// `count` bullets one at a time, each turned `step` further than the last.
+    Spiral {
+        count: usize,
+        step: f32,
+        interval: f32,
+    },
+    /// `count` bullets one at a time, each one at wherever the shooter aims by then.
+    Burst { count: usize, interval: f32 },
+}
+
+/// Bullets that fly straight for `delay` seconds, then turn towards the player for
+/// `duration` seconds.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Homing {
+    pub delay: f32,
+    pub duration: f32,
+    /// Degrees per second.
+    pub turn_rate: f32,
+}
+
+/// An enemy attack described as data, e.g. `(shape: Ring(count: 12), speed: 80.0)` in a
+/// `.waves.ron` file. Fired by inserting a [`FirePattern`].
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BulletPattern {
+    pub shape: PatternShape,
+    /// Pixels per second.
+    pub speed: f32,
+    pub damage: i32,
+    pub homing: Option<Homing>,
+}
+
+impl Default for BulletPattern {
+    fn default() -> Self {
+        Self {
+            shape: PatternShape::Fan {
+                count: 5,
+                spread: 20.0,
+            },
+            speed: 100.0,
+            damage: 1,
+            homing: None,
+        }
+    }
+}
+
+impl BulletPattern {
+    /// The default pattern with `count` bullets, like the fingers of a hand.
+    pub fn fan(count: usize) -> Self {
+        Self {
+            shape: PatternShape::Fan {
+                count,
+                spread: 20.0,
+            },
+            ..default()
+        }
+    }
+
+    /// Number of volleys, the bullets fired on the same tick.
+    pub fn volleys(&self) -> usize {
+        match self.shape {
+            PatternShape::Fan { .. } | PatternShape::Ring { .. } => 1,
+            PatternShape::Spiral { count, .. } | PatternShape::Burst { count, .. } => count,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        match self.shape {
+            PatternShape::Fan { .. } | PatternShape::Ring { .. } => Duration::ZERO,
+            PatternShape::Spiral { interval, .. } | PatternShape::Burst { interval, .. } => {
+                Duration::from_secs_f32(interval)
+            }
+        }
+    }
+
+    /// Whether every volley aims anew, instead of following the aim of the first one.
+    fn tracks_aim(&self) -> bool {
+        matches!(self.shape, PatternShape::Burst { .. })
+    }
+
+    /// Directions of the bullets of the `index`th volley.
+    pub fn volley(&self, index: usize, aim: Vec2) -> Vec<Vec2> {
+        match self.shape {
+            PatternShape::Fan { count, spread } => {
+                let first = -(count as f32 - 1.0) / 2.0;
+                (0..count)
+                    .map(|i| Vec2::from_angle((first + i as f32) * spread.to_radians()).rotate(aim))
+                    .collect()
+            }
+            PatternShape::Ring { count } => (0..count)
+                .map(|i| Vec2::from_angle(TAU * i as f32 / count as f32).rotate(aim))
+                .collect(),
+            PatternShape::Spiral { step, .. } => {
+                vec![Vec2::from_angle(step.to_radians() * index as f32).rotate(aim)]
+            }
+            PatternShape::Burst { .. } => vec![aim],
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.shape {
+            PatternShape::Spiral { interval, .. } | PatternShape::Burst { interval, .. }
+                if !(interval.is_finite() && interval > 0.0) =>
+            {
+                return Err(format!("`interval` must be above 0, found {interval}"));
+            }
+            PatternShape::Fan { spread: angle, .. } | PatternShape::Spiral { step: angle, .. }
+                if !angle.is_finite() =>
+            {
+                return Err("angles must be finite".into());
+            }
+            _ => {}
+        }
+        if !self.speed.is_finite() {
+            return Err("`speed` must be finite".into());
+        }
+        if let Some(Homing {
+            delay,
+            duration,
+            turn_rate,
+        }) = self.homing
+        {
+            if [delay, duration, turn_rate]
+                .iter()
+                .any(|value| !(value.is_finite() && *value >= 0.0))
+            {
+                return Err("`homing` values must be finite and not negative".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fires a [`BulletPattern`] from the entity along its [`AimDir`], removed once the last
+/// volley is out.
+#[derive(Component, Debug, Clone)]
+pub struct FirePattern {
+    pattern: BulletPattern,
+    sprite: Handle<Image>,
+    fired: usize,
+    /// Aim of the volleys that don't track the shooter's aim, set by the first one.
+    aim: Option<Vec2>,
+    timer: Timer,
+}
+
+impl FirePattern {
+    pub fn new(pattern: BulletPattern, sprite: Handle<Image>) -> Self {
+        Self {
+            pattern,
+            sprite,
+            fired: 0,
+            aim: None,
+            timer: Timer::new(pattern.interval(), TimerMode::Repeating),
+        }
+    }
+
+    /// Fires along `aim` instead of where the shooter aims when the first volley goes out.
+    pub fn aimed_at(mut self, aim: Vec2) -> Self {
+        self.aim = Some(aim);
+        self
+    }
+}
+
+/// Marks bullets fired by a [`FirePattern`].
+#[derive(Component, Debug)]
+pub struct EnemyBullet;
+
+/// Steers a bullet with [`Homing`], counting the time since it was fired.
+#[derive(Component, Debug)]
+struct HomingBullet {
+    homing: Homing,
+    elapsed: f32,
+}
+
+fn fire_patterns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shooters: Query<(Entity, &mut FirePattern, &Transform, &AimDir), Without<Dead>>,
+) {
+    for (entity, mut fire, transform, aim_dir) in shooters.iter_mut() {
+        if fire.fired > 0 {
+            fire.timer.tick(time.delta());
+            if !fire.timer.just_finished() {
+                continue;
+            }
+        }
+        let pattern = fire.pattern;
+        if fire.fired < pattern.volleys() {
+            let aim = if pattern.tracks_aim() {
+                **aim_dir
+            } else {
+                *fire.aim.get_or_insert(**aim_dir)
+            };
+            for dir in pattern.volley(fire.fired, aim) {
+                spawn_enemy_bullet(&mut commands, &pattern, &fire.sprite, transform, dir);
+            }
+            fire.fired += 1;
+        }
+        if fire.fired >= pattern.volleys() {
+            commands.entity(entity).try_remove::<FirePattern>();
+        }
+    }
+}
+
+fn spawn_enemy_bullet(
+    commands: &mut Commands,
+    pattern: &BulletPattern,
+    sprite: &Handle<Image>,
+    transform: &Transform,
+    dir: Vec2,
+) {
+    let mut bullet = commands.spawn(bullet_base(200.0));
+    bullet.insert((
+        BulletMaxWrap(1),
+        Damage(pattern.damage),
+        EnemyBullet,
+        Sprite {
+            image: sprite.clone(),
+            ..default()
+        },
+        Collider::ball(8.0),
+        CollidingEntities::default(),
+        CollisionGroups::new(ENEMY_HITBOX_GROUP, PLAYER_HURTBOX_GROUP),
+        EnemyHitbox,
+        *transform,
+        AimDir(dir),
+        AimDirRotationOffset(PI),
+        Velocity {
+            linvel: pattern.speed * dir,
+            ..default()
+        },
+    ));
+    if let Some(homing) = pattern.homing {
+        bullet.insert(HomingBullet {
+            homing,
+            elapsed: 0.0,
+        });
+    }
+}
+
+fn home_bullets(
+    mut commands: Commands,
+    mut bullets: Query<(
+        Entity,
+        &mut HomingBullet,
+        &mut Velocity,
+        &mut AimDir,
+        &Transform,
+    )>,
+    player: Single<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    for (bullet, mut homing, mut velocity, mut aim_dir, transform) in bullets.iter_mut() {
+        homing.elapsed += dt;
+        let Homing {
+            delay,
+            duration,
+            turn_rate,
+        } = homing.homing;
+        if homing.elapsed < delay {
+            continue;
+        }
+        if homing.elapsed > delay + duration {
+            commands.entity(bullet).try_remove::<HomingBullet>();
+            continue;
+        }
+        let to_player = (player.translation - transform.translation).xy();
+        let max_turn = turn_rate.to_radians() * dt;
+        let turn = velocity
+            .linvel
+            .angle_to(to_player)
+            .clamp(-max_turn, max_turn);
+        velocity.linvel = Vec2::from_angle(turn).rotate(velocity.linvel);
+        **aim_dir = velocity.linvel.normalize_or(**aim_dir);
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::ecs::component::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::{
+        APPROACH, Approach, Behaviour, BehaviourState, Charge, HOLD, Hold, Orbit, Retreat,
+        Steering, SteeringContext, Strafe,
+    };
+}
+
+/// Name of the state [`Behaviour::melee`] and [`Behaviour::ranged`] start in.
+pub const APPROACH: &str = "approach";
+/// Name of the state [`Behaviour::ranged`] switches to once the player is in range.
+pub const HOLD: &str = "hold";
+
+/// What an enemy knows about the player when it decides where to go.
+#[derive(Debug, Clone, Copy)]
+pub struct SteeringContext {
+    /// Unit vector from the enemy to the player.
+    pub direction: Vec2,
+    pub distance: f32,
+    pub speed: f32,
+    /// Time since the enemy entered its current state.
+    pub elapsed: Duration,
+}
+
+/// How an enemy moves while it's in a [`BehaviourState`].
+pub trait Steering: Send + Sync + 'static {
+    /// The velocity the enemy speeds up to, `None` lets it slow down to a stop.
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2>;
+}
+
+/// Goes straight for the player.
+pub struct Approach;
+
+impl Steering for Approach {
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2> {
+        Some(context.direction * context.speed)
+    }
+}
+
+/// Stands still, for keeping a distance or winding up an attack.
+pub struct Hold;
+
+impl Steering for Hold {
+    fn steer(&self, _: &SteeringContext) -> Option<Vec2> {
+        None
+    }
+}
+
+/// Runs straight away from the player.
+pub struct Retreat;
+
+impl Steering for Retreat {
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2> {
+        Some(-context.direction * context.speed)
+    }
+}
+
+/// Circles the player counterclockwise, drifting towards `radius`.
+pub struct Orbit {
+    pub radius: f32,
+}
+
+impl Steering for Orbit {
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2> {
+        let drift = ((context.distance - self.radius) / self.radius).clamp(-1.0, 1.0);
+        let dir = context.direction.perp() + context.direction * drift;
+        Some(dir.normalize_or_zero() * context.speed)
+    }
+}
+
+/// Moves sideways to the player, switching sides every `period`.
+pub struct Strafe {
+    pub period: Duration,
+}
+
+impl Steering for Strafe {
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2> {
+        let side = if (context.elapsed.as_secs_f32() / self.period.as_secs_f32()) as u32 % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        Some(context.direction.perp() * side * context.speed)
+    }
+}
+
+/// Rushes the player, faster than the enemy's [`Speed`](crate::characters::Speed).
+pub struct Charge {
+    pub speed_multiplier: f32,
+}
+
+impl Steering for Charge {
+    fn steer(&self, context: &SteeringContext) -> Option<Vec2> {
+        Some(context.direction * context.speed * self.speed_multiplier)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Transition {
+    After(Duration, &'static str),
+    PlayerWithin(f32, &'static str),
+    PlayerBeyond(f32, &'static str),
+}
+
+impl Transition {
+    fn target(self, distance: f32, elapsed: Duration) -> Option<&'static str> {
+        match self {
+            Transition::After(duration, to) if elapsed >= duration => Some(to),
+            Transition::PlayerWithin(range, to) if distance <= range => Some(to),
+            Transition::PlayerBeyond(range, to) if distance > range => Some(to),
+            _ => None,
+        }
+    }
+
+    fn to(self) -> &'static str {
+        match self {
+            Transition::After(_, to)
+            | Transition::PlayerWithin(_, to)
+            | Transition::PlayerBeyond(_, to) => to,
+        }
+    }
+}
+
+/// A named state of a [`Behaviour`], with the transitions out of it in the order they're
+/// checked.
+#[derive(Clone)]
+pub struct BehaviourState {
+    name: &'static str,
+    steering: Arc<dyn Steering>,
+    transitions: Vec<Transition>,
+}
+
+impl BehaviourState {
+    pub fn new(name: &'static str, steering: impl Steering) -> Self {
+        Self {
+            name,
+            steering: Arc::new(steering),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Switches to `to` once the enemy spent `duration` in this state.
+    pub fn after(mut self, duration: Duration, to: &'static str) -> Self {
+        self.transitions.push(Transition::After(duration, to));
+        self
+    }
+
+    /// Switches to `to` once the player is at most `range` away.
+    pub fn player_within(mut self, range: f32, to: &'static str) -> Self {
+        self.transitions.push(Transition::PlayerWithin(range, to));
+        self
+    }
+
+    /// Switches to `to` once the player is further than `range` away.
+    pub fn player_beyond(mut self, range: f32, to: &'static str) -> Self {
+        self.transitions.push(Transition::PlayerBeyond(range, to));
+        self
+    }
+}
+
+/// The state machine deciding how an enemy moves, driven by `boids_move_towards_player`.
+/// It starts in its first state, and is [validated](Behaviour::validate) once inserted.
+#[derive(Component, Clone)]
+#[component(on_insert = validate_behaviour)]
+pub struct Behaviour {
+    states: Vec<BehaviourState>,
+    current: usize,
+    elapsed: Duration,
+}
+
+impl Default for Behaviour {
+    fn default() -> Self {
+        Self::melee()
+    }
+}
+
+impl Behaviour {
+    pub fn new(initial: BehaviourState) -> Self {
+        Self {
+            states: vec![initial],
+            current: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Adds a state to switch to.
+    pub fn with(mut self, state: BehaviourState) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Goes towards the player.
+    pub fn melee() -> Self {
+        Self::new(BehaviourState::new(APPROACH, Approach))
+    }
+
+    /// Goes towards the player until it's `max_range` away, then stays put.
+    pub fn ranged(max_range: f32) -> Self {
+        Self::new(BehaviourState::new(APPROACH, Approach).player_within(max_range, HOLD))
+            .with(BehaviourState::new(HOLD, Hold).player_beyond(max_range, APPROACH))
+    }
+
+    /// Name of the current state.
+    pub fn state(&self) -> &'static str {
+        self.states[self.current].name
+    }
+
+    /// Checks that no two states share a name and that every transition leads to a state.
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, state) in self.states.iter().enumerate() {
+            if self.states[..index]
+                .iter()
+                .any(|other| other.name == state.name)
+            {
+                return Err(format!("more than one state is named `{}`", state.name));
+            }
+            for transition in &state.transitions {
+                let to = transition.to();
+                if !self.states.iter().any(|other| other.name == to) {
+                    return Err(format!(
+                        "`{}` switches to `{to}`, which isn't a state",
+                        state.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Switches to the state named `name` right away, even if it's the current one.
+    pub fn switch_to(&mut self, name: &'static str) {
+        let Some(index) = self.states.iter().position(|state| state.name == name) else {
+            debug_assert!(false, "no behaviour state named `{name}`");
+            tracing::warn!(name, "no behaviour state with this name");
+            return;
+        };
+        self.current = index;
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Advances the time spent in the current state, takes the first transition out of it
+    /// that applies and returns how the enemy should move.
+    pub fn steer(
+        &mut self,
+        delta: Duration,
+        direction: Vec2,
+        distance: f32,
+        speed: f32,
+    ) -> Option<Vec2> {
+        self.elapsed += delta;
+        let next = self.states[self.current]
+            .transitions
+            .iter()
+            .find_map(|transition| transition.target(distance, self.elapsed));
+        if let Some(next) = next {
+            self.switch_to(next);
+        }
+        let context = SteeringContext {
+            direction,
+            distance,
+            speed,
+            elapsed: self.elapsed,
+        };
+        self.states[self.current].steering.steer(&context)
+    }
+}
+
+fn validate_behaviour(world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(behaviour) = world.get::<Behaviour>(entity) else {
+        return;
+    };
+    if let Err(err) = behaviour.validate() {
+        debug_assert!(false, "invalid behaviour on {entity}: {err}");
+        tracing::error!(?entity, %err, "invalid behaviour");
+    }
+}
@@ -0,0 +1,397 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use crate::audio::prelude::*;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_trauma_shake::prelude::*;
+use bon::Builder;
+
+use crate::ShakeExt;
+use crate::characters::bullet::patterns::prelude::*;
+use crate::characters::enemies::behaviour::prelude::*;
+use crate::characters::enemies::coffin::prelude::*;
+use crate::characters::enemies::ghost::prelude::*;
+use crate::characters::enemies::hand::HandAssets;
+use crate::characters::enemies::prelude::*;
+use crate::characters::{AimDirRotationOffset, prelude::*};
+use crate::effects::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::boss_plugin;
+    pub use super::{Boss, BossArgs, CommandsBoss};
+}
+
+pub fn boss_plugin(app: &mut App) {
+    app.add_systems(OnExit(GameScreen::Gameplay), reset_time_speed)
+        .add_systems(
+            FixedUpdate,
+            (
+                boss_switch_phase,
+                boss_attack.after(boss_switch_phase),
+                boss_start_death_cinematic,
+                boss_death_cinematic.after(boss_start_death_cinematic),
+            )
+                .run_if(not(in_state(GameScreen::SplashFirst))),
+        );
+}
+
+/// How far the boss keeps from the player when it isn't charging.
+const BOSS_RANGE: f32 = 96.0;
+/// Speed multiplier while charging at the player.
+const BOSS_CHARGE_SPEED: f32 = 4.0;
+const BOSS_CHARGE_DURATION: Duration = Duration::from_millis(600);
+/// Time the boss stands still before a charge.
+const BOSS_TELEGRAPH_DURATION: Duration = Duration::from_millis(400);
+const ORBIT: &str = "orbit";
+const TELEGRAPH: &str = "telegraph";
+const CHARGE: &str = "charge";
+/// Distance from the boss at which its ghosts are summoned.
+const BOSS_SUMMON_SPACING: f32 = 40.0;
+/// Relative speed of virtual time while the boss dies.
+const BOSS_DEATH_SLOWDOWN: f64 = 0.25;
+const BOSS_DEATH_DURATION: Duration = Duration::from_millis(800);
+/// Fired all around the boss whenever a new phase starts.
+const PHASE_RING: BulletPattern = BulletPattern {
+    shape: PatternShape::Ring { count: 16 },
+    speed: 80.0,
+    damage: 1,
+    homing: None,
+};
+const HOMING_BURST: BulletPattern = BulletPattern {
+    shape: PatternShape::Burst {
+        count: 3,
+        interval: 0.2,
+    },
+    speed: 80.0,
+    damage: 1,
+    homing: Some(Homing {
+        delay: 0.4,
+        duration: 1.2,
+        turn_rate: 90.0,
+    }),
+};
+const SPIRAL: BulletPattern = BulletPattern {
+    shape: PatternShape::Spiral {
+        count: 24,
+        step: 30.0,
+        interval: 0.03,
+    },
+    speed: 90.0,
+    damage: 1,
+    homing: None,
+};
+/// Side of the boss' sprite, it's drawn with the hand's texture.
+const BOSS_SIZE: f32 = 64.0;
+
+/// A large enemy that cycles through the attacks of its current [`BossPhase`].
+#[derive(Component, Builder)]
+pub struct Boss {
+    finger_count: usize,
+    summon_count: usize,
+    #[builder(skip)]
+    phase: BossPhase,
+    /// Index of the next attack in the phase's attack list.
+    #[builder(skip)]
+    next_attack: usize,
+    #[builder(skip = Timer::new(BossPhase::default().attack_rate(), TimerMode::Repeating))]
+    attack_timer: Timer,
+}
+
+/// The stages of a boss fight, the next one starts every time the boss loses a third of its
+/// health.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum BossPhase {
+    #[default]
+    Fingers,
+    Summons,
+    Frenzy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BossAttack {
+    /// A fan of fingers, like the ones hands shoot.
+    FingerFan,
+    /// Ghosts that speed up, like the ones coffins summon.
+    SummonGhosts,
+    /// Fingers fired one after the other that turn towards the player.
+    HomingBurst,
+    /// Two turns of fingers fired one after the other.
+    Spiral,
+    /// A short rush at the player.
+    Charge,
+}
+
+impl BossPhase {
+    fn from_health(health: i32, max_health: i32) -> Self {
+        let fraction = health as f32 / max_health.max(1) as f32;
+        if fraction > 2.0 / 3.0 {
+            BossPhase::Fingers
+        } else if fraction > 1.0 / 3.0 {
+            BossPhase::Summons
+        } else {
+            BossPhase::Frenzy
+        }
+    }
+
+    fn attacks(self) -> &'static [BossAttack] {
+        match self {
+            BossPhase::Fingers => &[BossAttack::FingerFan],
+            BossPhase::Summons => &[
+                BossAttack::FingerFan,
+                BossAttack::SummonGhosts,
+                BossAttack::HomingBurst,
+            ],
+            BossPhase::Frenzy => &[
+                BossAttack::Charge,
+                BossAttack::Spiral,
+                BossAttack::SummonGhosts,
+                BossAttack::FingerFan,
+                BossAttack::HomingBurst,
+            ],
+        }
+    }
+
+    fn attack_rate(self) -> Duration {
+        match self {
+            BossPhase::Fingers => Duration::from_millis(1500),
+            BossPhase::Summons => Duration::from_millis(1300),
+            BossPhase::Frenzy => Duration::from_millis(900),
+        }
+    }
+}
+
+#[derive(Component, Builder)]
+pub struct BossArgs<'a> {
+    assets: &'a Res<'a, HandAssets>,
+    boss: Boss,
+    health: i32,
+}
+
+pub trait CommandsBoss<T> {
+    fn spawn_boss(&'_ mut self, args: T) -> EntityCommands<'_>;
+}
+
+impl<'w, 's> CommandsBoss<BossArgs<'_>> for Commands<'w, 's> {
+    fn spawn_boss(&'_ mut self, args: BossArgs<'_>) -> EntityCommands<'_> {
+        let mut commands = self.spawn(enemy_base());
+        commands
+            .insert((
+                Sprite {
+                    image: args.assets.sprite.clone(),
+                    custom_size: Some(Vec2::splat(BOSS_SIZE)),
+                    color: Color::srgb(1.0, 0.6, 0.6),
+                    ..default()
+                },
+                args.boss,
+            ))
+            .insert(EnemyKind::Boss)
+            .insert(Health(args.health))
+            .insert(MaxHealth(args.health))
+            .insert(Speed(40.0))
+            .insert(ColliderDebugColor(Hsla::new(0.0, 0.0, 0.0, 0.0)))
+            .insert(Collider::ball(BOSS_SIZE / 2.0 - 8.0))
+            .insert(boss_behaviour())
+            .insert(AimDirRotationOffset(-PI))
+            .with_children(|parent| {
+                parent.spawn((
+                    EnemyHitbox,
+                    Transform::default(),
+                    Sensor,
+                    Collider::ball(BOSS_SIZE / 2.0 - 12.0),
+                    Damage(1),
+                    CollisionGroups::new(ENEMY_HITBOX_GROUP, PLAYER_HURTBOX_GROUP),
+                    ActiveEvents::COLLISION_EVENTS,
+                    ColliderDebugColor(Hsla::hsl(340., 1.0, 0.8)),
+                ));
+            });
+        commands
+    }
+}
+
+/// Circles the player once in range, charges go through a telegraph first and end in
+/// an approach.
+fn boss_behaviour() -> Behaviour {
+    Behaviour::new(BehaviourState::new(APPROACH, Approach).player_within(BOSS_RANGE, ORBIT))
+        .with(
+            BehaviourState::new(ORBIT, Orbit { radius: BOSS_RANGE })
+                .player_beyond(BOSS_RANGE * 1.5, APPROACH),
+        )
+        .with(BehaviourState::new(TELEGRAPH, Hold).after(BOSS_TELEGRAPH_DURATION, CHARGE))
+        .with(
+            BehaviourState::new(
+                CHARGE,
+                Charge {
+                    speed_multiplier: BOSS_CHARGE_SPEED,
+                },
+            )
+            .after(BOSS_CHARGE_DURATION, APPROACH),
+        )
+}
+
+/// A new phase opens with [`PHASE_RING`], cutting off whatever pattern the boss was firing.
+fn boss_switch_phase(
+    mut commands: Commands,
+    mut bosses: Query<(Entity, &mut Boss, &Health, &MaxHealth), Without<Dead>>,
+    hand_assets: Res<HandAssets>,
+    mut shake: Single<&mut Shake>,
+) {
+    for (entity, mut boss, health, max_health) in bosses.iter_mut() {
+        let phase = BossPhase::from_health(**health, **max_health);
+        if phase == boss.phase {
+            continue;
+        }
+        tracing::info!(?entity, ?phase, "boss phase");
+        boss.phase = phase;
+        boss.next_attack = 0;
+        boss.attack_timer = Timer::new(phase.attack_rate(), TimerMode::Repeating);
+        commands.entity(entity).try_insert((
+            GhostSpriteSpawner::builder()
+                .kind(GhostSpriteSpawnerKind::Count(6))
+                .rate(0.05)
+                .ghost_decay(6.0)
+                .build(),
+            FirePattern::new(PHASE_RING, hand_assets.finger.clone()),
+        ));
+        shake.apply_trauma(0.5);
+    }
+}
+
+/// The attack timer waits while a [`FirePattern`] is still going, so attacks never cut each
+/// other off.
+fn boss_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bosses: Query<
+        (
+            Entity,
+            &mut Boss,
+            &mut Behaviour,
+            &Transform,
+            Has<FirePattern>,
+        ),
+        Without<Dead>,
+    >,
+    hand_assets: Res<HandAssets>,
+    ghost_assets: Res<GhostAssets>,
+    coffin_assets: Res<CoffinAssets>,
+    sfx: Res<AudioChannel<Sfx>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let rng = rng.spawning();
+    for (entity, mut boss, mut behaviour, transform, firing) in bosses.iter_mut() {
+        // running patterns and charges hold off the next attack
+        if firing || [TELEGRAPH, CHARGE].contains(&behaviour.state()) {
+            continue;
+        }
+        boss.attack_timer.tick(time.delta());
+        if !boss.attack_timer.just_finished() {
+            continue;
+        }
+        let attacks = boss.phase.attacks();
+        let attack = attacks[boss.next_attack % attacks.len()];
+        boss.next_attack += 1;
+        tracing::debug!(?entity, ?attack, "boss attack");
+        let fire = |pattern| FirePattern::new(pattern, hand_assets.finger.clone());
+        match attack {
+            BossAttack::FingerFan => {
+                commands
+                    .entity(entity)
+                    .try_insert(fire(BulletPattern::fan(boss.finger_count)));
+            }
+            BossAttack::HomingBurst => {
+                commands.entity(entity).try_insert(fire(HOMING_BURST));
+            }
+            BossAttack::Spiral => {
+                commands.entity(entity).try_insert(fire(SPIRAL));
+            }
+            BossAttack::SummonGhosts => {
+                for _ in 0..boss.summon_count {
+                    summon_ghost(
+                        &mut commands,
+                        &ghost_assets,
+                        entity,
+                        transform,
+                        BOSS_SUMMON_SPACING,
+                        rng,
+                    );
+                }
+                sfx.play(coffin_assets.spawn_sound.clone());
+            }
+            BossAttack::Charge => {
+                behaviour.switch_to(TELEGRAPH);
+                commands.entity(entity).try_insert(
+                    GhostSpriteSpawner::builder()
+                        .kind(GhostSpriteSpawnerKind::Time(
+                            (BOSS_TELEGRAPH_DURATION + BOSS_CHARGE_DURATION).as_secs_f32(),
+                        ))
+                        .rate(0.03)
+                        .ghost_decay(8.0)
+                        .build(),
+                );
+            }
+        }
+    }
+}
+
+/// Runs while a dead boss slowly fades out, it's despawned once the timer finishes.
+#[derive(Component, Deref, DerefMut)]
+struct DeathCinematic(Timer);
+
+fn boss_start_death_cinematic(
+    mut commands: Commands,
+    bosses: Query<Entity, (With<Boss>, Added<Dead>)>,
+    mut time: ResMut<Time<Virtual>>,
+    mut duck: EventWriter<DuckMusic>,
+    mut shake: Single<&mut Shake>,
+) {
+    for boss in bosses.iter() {
+        tracing::info!(?boss, "boss died");
+        // the hitbox goes first so the dying boss can't hurt the player anymore
+        commands
+            .entity(boss)
+            .despawn_related::<Children>()
+            .try_insert((
+                DeathCinematic(Timer::new(BOSS_DEATH_DURATION, TimerMode::Once)),
+                GhostSpriteSpawner::builder()
+                    .kind(GhostSpriteSpawnerKind::Infinite)
+                    .rate(0.02)
+                    .ghost_decay(4.0)
+                    .build(),
+            ));
+        time.set_relative_speed_f64(BOSS_DEATH_SLOWDOWN);
+        duck.write(DuckMusic {
+            volume: 0.2,
+            duration: Duration::from_secs(3),
+        });
+        shake.apply_trauma(0.6);
+    }
+}
+
+fn boss_death_cinematic(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bosses: Query<(Entity, &mut DeathCinematic, &mut Sprite)>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut shake: Single<&mut Shake>,
+) {
+    for (boss, mut cinematic, mut sprite) in bosses.iter_mut() {
+        cinematic.tick(time.delta());
+        let progress = cinematic.fraction();
+        sprite.custom_size = Some(Vec2::splat(BOSS_SIZE * (1.0 + progress)));
+        sprite.color.set_alpha(1.0 - progress);
+        shake.apply_trauma(0.05);
+        if cinematic.finished() {
+            commands.entity(boss).try_despawn();
+            virtual_time.set_relative_speed_f64(1.0);
+            shake.apply_trauma(0.8);
+        }
+    }
+}
+
+/// A run can end in the middle of a boss' death.
+fn reset_time_speed(mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed_f64(1.0);
+}
@@ -5,18 +5,21 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bon::Builder;
-use rand::{Rng, rng};
+use rand::Rng;
 
 use crate::characters::enemies::ghost::{CommandsGhost, prelude::*};
 use crate::characters::enemies::prelude::*;
 use crate::characters::{SpeedMod, prelude::*};
 use crate::effects::prelude::*;
 use crate::exp_decay::ExpDecay;
+use crate::rng::prelude::*;
 use crate::screens::prelude::*;
 
 pub mod prelude {
     pub use super::coffin_plugin;
-    pub use super::{Coffin, CoffinArgs, CoffinAssets, CommandsCoffin};
+    pub use super::{
+        Coffin, CoffinArgs, CoffinAssets, CommandsCoffin, SpawnedByCoffin, summon_ghost,
+    };
 }
 
 pub fn coffin_plugin(app: &mut App) {
@@ -24,7 +27,7 @@ pub fn coffin_plugin(app: &mut App) {
         LoadingStateConfig::new(GameScreen::SplashFirst).load_collection::<CoffinAssets>(),
     )
     .add_systems(
-        Update,
+        FixedUpdate,
         (coffin_spawn_ghosts, coffin_ghosts_speed_up)
             .run_if(not(in_state(GameScreen::SplashFirst))),
     );
@@ -71,10 +74,11 @@ impl<'w, 's> CommandsCoffin<CoffinArgs<'_>> for Commands<'w, 's> {
                 },
                 args.coffin,
             ))
+            .insert(EnemyKind::Coffin)
             .insert(Health(15))
             .insert(Speed(48.0))
             .insert(ColliderDebugColor(Hsla::new(0.0, 0.0, 0.0, 0.0)))
-            .insert(EnemyClass::Ranged { max_range: 64.0 })
+            .insert(Behaviour::ranged(64.0))
             .insert(
                 GhostSpriteSpawner::builder()
                     .kind(GhostSpriteSpawnerKind::Infinite)
@@ -97,42 +101,60 @@ impl<'w, 's> CommandsCoffin<CoffinArgs<'_>> for Commands<'w, 's> {
     }
 }
 
-#[derive(Component)]
-struct SpawnedByCoffin;
+/// Marks ghosts summoned by a coffin or a boss, pointing back to it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnedByCoffin(pub Entity);
 
 fn coffin_spawn_ghosts(
     mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(&mut Coffin, &Transform)>,
+    mut query: Query<(Entity, &mut Coffin, &Transform)>,
     ghost_assets: Res<GhostAssets>,
     coffin_assets: Res<CoffinAssets>,
-    audio: Res<Audio>,
-    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (mut coffin, transform) in query.iter_mut() {
+    let rng = rng.spawning();
+    for (coffin_id, mut coffin, transform) in query.iter_mut() {
         coffin.initial_rate_timer.tick(time.delta());
         if coffin.initial_rate_timer.finished() {
             coffin.spawn_rate_timer.tick(time.delta());
         }
         if coffin.spawn_rate_timer.just_finished() || coffin.initial_rate_timer.just_finished() {
             for _ in 0..coffin.count {
-                let dir = vec2(rng().random_range(0.0..1.0), rng().random_range(0.0..1.0))
-                    .normalize_or(vec2(1.0, 0.0));
-                commands
-                    .spawn_ghost(GhostArgs::builder().assets(&ghost_assets).build())
-                    .insert(transform.with_translation(
-                        transform.translation + (dir * coffin.spacing).extend(0.0),
-                    ))
-                    .insert(SpeedMod(0.0))
-                    .insert(SpawnedByCoffin);
-                audio
-                    .play(coffin_assets.spawn_sound.clone())
-                    .with_volume(volume.calc_sfx(1.0));
+                summon_ghost(
+                    &mut commands,
+                    &ghost_assets,
+                    coffin_id,
+                    transform,
+                    coffin.spacing,
+                    rng,
+                );
+                sfx.play(coffin_assets.spawn_sound.clone());
             }
         }
     }
 }
 
+/// Spawns a ghost `spacing` away from `summoner`, standing still at first and then
+/// speeding up to its full speed.
+pub fn summon_ghost(
+    commands: &mut Commands,
+    ghost_assets: &Res<GhostAssets>,
+    summoner: Entity,
+    transform: &Transform,
+    spacing: f32,
+    rng: &mut impl Rng,
+) {
+    let dir =
+        vec2(rng.random_range(0.0..1.0), rng.random_range(0.0..1.0)).normalize_or(vec2(1.0, 0.0));
+    commands
+        .spawn_ghost(GhostArgs::builder().assets(ghost_assets).build())
+        .insert(transform.with_translation(transform.translation + (dir * spacing).extend(0.0)))
+        .insert(SpeedMod(0.0))
+        .insert(SpawnedByCoffin(summoner));
+}
+
 fn coffin_ghosts_speed_up(mut query: Query<&mut SpeedMod, With<SpawnedByCoffin>>, time: Res<Time>) {
     let dt = time.delta_secs();
     for mut speed_mod in query.iter_mut() {
@@ -47,10 +47,11 @@ impl<'w, 's> CommandsGhost<GhostArgs<'_>> for Commands<'w, 's> {
                 },
                 Ghost,
             ))
+            .insert(EnemyKind::Ghost)
             .insert(Health(2))
             .insert(Speed(64.0))
             .insert(ColliderDebugColor(Hsla::new(0.0, 0.0, 0.0, 0.0)))
-            .insert(EnemyClass::Melee)
+            .insert(Behaviour::melee())
             .with_children(|parent| {
                 parent.spawn((
                     EnemyHitbox,
@@ -1,15 +1,17 @@
 use std::f32::consts::PI;
 use std::time::Duration;
 
+use crate::COLORS;
 use crate::audio::prelude::*;
 use crate::autotimer::{AutoTimer, TimerRepeating};
-use crate::characters::bullet::{BulletMaxWrap, bullet_base};
+use bevy::gizmos::config::GizmoConfigStore;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bon::Builder;
-use rand::{Rng, rng};
 
+use crate::characters::bullet::patterns::prelude::*;
+use crate::characters::enemies::behaviour::prelude::*;
 use crate::characters::enemies::ghost::{CommandsGhost, prelude::*};
 use crate::characters::enemies::prelude::*;
 use crate::characters::{AimDirRotationOffset, SpeedMod, prelude::*};
@@ -24,8 +26,13 @@ pub fn hand_plugin(app: &mut App) {
         LoadingStateConfig::new(GameScreen::SplashFirst).load_collection::<HandAssets>(),
     )
     .add_systems(
-        Update,
+        FixedUpdate,
         (hand_shoot_fingers).run_if(not(in_state(GameScreen::SplashFirst))),
+    )
+    .add_systems(
+        Update,
+        // headless runs have no gizmos to draw the aim lines with
+        hand_wind_up_effects.run_if(resource_exists::<GizmoConfigStore>),
     );
 }
 
@@ -34,10 +41,22 @@ pub struct Hand {
     #[builder(with = |shoot_rate: Duration| Timer::new(shoot_rate, TimerMode::Repeating))]
     #[builder(name = shoot_rate)]
     shoot_rate_timer: Timer,
-    #[builder(default = 5)]
-    finger_count: usize,
+    #[builder(default)]
+    pattern: BulletPattern,
+    /// How long before a volley the hand locks its aim and shows where the fingers will go.
+    #[builder(default = Duration::from_millis(500))]
+    wind_up: Duration,
 }
 
+/// A hand about to shoot, its [`AimDir`] stays locked until the volley.
+#[derive(Component, Debug)]
+pub struct WindUp;
+
+/// Length of the aim lines shown during a wind up.
+const AIM_LINE_LENGTH: f32 = 96.0;
+const STRAFE: &str = "strafe";
+const RETREAT: &str = "retreat";
+
 #[derive(AssetCollection, Resource)]
 pub struct HandAssets {
     #[asset(path = "enemies/hand.png")]
@@ -67,11 +86,12 @@ impl<'w, 's> CommandsHand<HandArgs<'_>> for Commands<'w, 's> {
                 },
                 args.hand,
             ))
+            .insert(EnemyKind::Hand)
             .insert(Health(20))
             .insert(Speed(48.0))
             .insert(ColliderDebugColor(Hsla::new(0.0, 0.0, 0.0, 0.0)))
             .insert(Collider::cuboid(16., 16.))
-            .insert(EnemyClass::Ranged { max_range: 96.0 })
+            .insert(hand_behaviour())
             .insert(AimDirRotationOffset(-PI))
             .with_children(|parent| {
                 parent.spawn((
@@ -88,48 +108,72 @@ impl<'w, 's> CommandsHand<HandArgs<'_>> for Commands<'w, 's> {
     }
 }
 
-#[derive(Component)]
-pub struct Finger;
+/// Gets within range, then keeps moving sideways to be harder to hit, backing off when the
+/// player comes too close.
+fn hand_behaviour() -> Behaviour {
+    const RANGE: f32 = 96.0;
+    const TOO_CLOSE: f32 = 48.0;
+    Behaviour::new(BehaviourState::new(APPROACH, Approach).player_within(RANGE, STRAFE))
+        .with(
+            BehaviourState::new(
+                STRAFE,
+                Strafe {
+                    period: Duration::from_secs(2),
+                },
+            )
+            .player_within(TOO_CLOSE, RETREAT)
+            .player_beyond(RANGE, APPROACH),
+        )
+        .with(BehaviourState::new(RETREAT, Retreat).player_beyond(RANGE - 16.0, STRAFE))
+}
 
 fn hand_shoot_fingers(
     mut commands: Commands,
     time: Res<Time>,
-    mut hands: Query<(&mut Hand, &Transform, &AimDir)>,
+    mut hands: Query<(Entity, &mut Hand, &AimDir, Has<WindUp>), Without<Dead>>,
     assets: Res<HandAssets>,
 ) {
-    for (mut hand, transform, aim_dir) in hands.iter_mut() {
+    for (entity, mut hand, aim_dir, winding_up) in hands.iter_mut() {
         hand.shoot_rate_timer.tick(time.delta());
-        if !hand.shoot_rate_timer.just_finished() {
+        if hand.shoot_rate_timer.just_finished() {
+            // aimed now, the hand follows the player again before the pattern fires
+            let fire = FirePattern::new(hand.pattern, assets.finger.clone()).aimed_at(**aim_dir);
+            commands
+                .entity(entity)
+                .try_remove::<WindUp>()
+                .try_insert((fire, LookAtPlayer));
+        } else if !winding_up && hand.shoot_rate_timer.remaining() <= hand.wind_up {
+            commands
+                .entity(entity)
+                .try_remove::<LookAtPlayer>()
+                .try_insert(WindUp);
+        }
+    }
+}
+
+/// Pulses the hand's tint faster and faster and fades in a line along the path of every
+/// bullet of the first volley.
+fn hand_wind_up_effects(
+    mut hands: Query<(&Hand, &Transform, &AimDir, &mut Sprite, Has<WindUp>)>,
+    mut gizmos: Gizmos,
+) {
+    for (hand, transform, aim_dir, mut sprite, winding_up) in hands.iter_mut() {
+        if !winding_up {
+            sprite.color = Color::WHITE;
             continue;
         }
-        let spread = 20.0f32.to_radians();
-        let half_finger_count = hand.finger_count as i32 / 2;
-        for idx in -half_finger_count..half_finger_count {
-            let current_angle_base = -spread * (idx) as f32;
-            let next_aim_dir = Vec2::from_angle(current_angle_base).rotate(**aim_dir);
-            commands
-                .spawn(bullet_base(200.0))
-                .insert(BulletMaxWrap(1))
-                .insert(Damage(1))
-                .insert(Finger)
-                .insert(Sprite {
-                    image: assets.finger.clone(),
-                    ..default()
-                })
-                .insert(Collider::ball(8.0))
-                .insert(CollidingEntities::default())
-                .insert(CollisionGroups::new(
-                    ENEMY_HITBOX_GROUP,
-                    PLAYER_HURTBOX_GROUP,
-                ))
-                .insert(EnemyHitbox)
-                .insert(*transform)
-                .insert(AimDir(next_aim_dir))
-                .insert(AimDirRotationOffset(PI))
-                .insert(Velocity {
-                    linvel: 100.0 * next_aim_dir,
-                    ..default()
-                });
+        let remaining = hand.shoot_rate_timer.remaining().as_secs_f32();
+        let wind_up = hand.wind_up.as_secs_f32().max(f32::EPSILON);
+        let progress = (1.0 - remaining / wind_up).clamp(0.0, 1.0);
+        let pulse = (progress * progress * 6.0 * PI).sin().abs();
+        sprite.color = Color::WHITE.mix(&COLORS[1], pulse);
+        let start = transform.translation.xy();
+        for dir in hand.pattern.volley(0, **aim_dir) {
+            gizmos.line_2d(
+                start,
+                start + dir * AIM_LINE_LENGTH,
+                COLORS[1].with_alpha(0.4 * progress),
+            );
         }
     }
 }
@@ -1,17 +1,21 @@
 use std::f32::consts::PI;
 use std::marker::PhantomData;
-use std::sync::Arc;
 use std::time::Duration;
 
 use crate::audio::prelude::*;
+use crate::characters::enemies::behaviour::prelude::*;
+use crate::characters::enemies::boss::prelude::*;
 use crate::characters::enemies::ghost::CommandsGhost;
 use crate::characters::enemies::hand::hand_plugin;
+use crate::characters::enemies::spatial_hash::prelude::*;
+use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_enoki::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_trauma_shake::prelude::*;
 use bon::Builder;
+use rand::Rng;
 use tracing::instrument;
 
 use crate::ShakeExt;
@@ -21,24 +25,34 @@ use crate::characters::enemies::coffin::prelude::*;
 use crate::characters::enemies::ghost::prelude::*;
 use crate::characters::player::Player;
 use crate::characters::prelude::*;
+use crate::rng::prelude::*;
 use crate::screens::GameScreen;
 
 pub mod prelude {
+    pub use super::behaviour::Behaviour;
     pub use super::despawn_enemies;
     pub use super::enemies_plugin;
     pub use super::enemy_base;
-    pub use super::{Enemy, EnemyClass, EnemyHitbox, EnemyHurtbox};
+    pub use super::handle_enemy_died_events;
+    pub use super::{
+        Enemy, EnemyDiedEvent, EnemyHitEvent, EnemyHitbox, EnemyHurtbox, EnemyKind, HitOnce,
+    };
 }
 
+pub mod behaviour;
+pub mod boss;
 pub mod coffin;
 pub mod ghost;
 pub mod hand;
+pub mod spatial_hash;
 
 pub fn enemies_plugin(app: &mut App) {
     app.insert_resource(BoidSeparationUpdateRate::PerFrame)
+        .init_resource::<SpatialHash>()
         .add_plugins(ghost_plugin)
         .add_plugins(coffin_plugin)
         .add_plugins(hand_plugin)
+        .add_plugins(boss_plugin)
         .configure_loading_state(
             LoadingStateConfig::new(GameScreen::SplashFirst).load_collection::<EnemyAssets>(),
         )
@@ -51,9 +65,12 @@ pub fn enemies_plugin(app: &mut App) {
             FixedUpdate,
             (
                 on_player_died,
-                boids_calculate_separation,
+                (rebuild_spatial_hash, boids_calculate_separation)
+                    .chain()
+                    .run_if(boid_separation_due),
                 boids_move_towards_player.after(boids_calculate_separation),
                 enemy_check_for_player_collisions,
+                forget_passed_hurtboxes.before(enemies_take_damage),
                 enemies_take_damage,
                 handle_enemy_died_events.after(enemies_take_damage),
                 handle_enemy_hit_events
@@ -65,9 +82,29 @@ pub fn enemies_plugin(app: &mut App) {
 }
 
 #[derive(Component, Debug)]
-#[require(Boid, EnemyClass)]
+#[require(Boid, Behaviour)]
 pub struct Enemy;
 
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumIter)]
+pub enum EnemyKind {
+    Ghost,
+    Coffin,
+    Hand,
+    Boss,
+}
+
+impl EnemyKind {
+    /// Lowercase plural, for listing kills.
+    pub fn plural(self) -> &'static str {
+        match self {
+            EnemyKind::Ghost => "ghosts",
+            EnemyKind::Coffin => "coffins",
+            EnemyKind::Hand => "hands",
+            EnemyKind::Boss => "bosses",
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 #[require(Hitbox)]
 pub struct EnemyHitbox;
@@ -96,41 +133,35 @@ pub struct Boid {
 }
 
 impl Boid {
-    fn calculate_separation<'a>(
-        &self,
-        transform: &Transform,
-        transforms: impl Iterator<Item = &'a Transform>,
-    ) -> Vec2 {
-        let flock_detection_range_squared = self.flock_detection_range * self.flock_detection_range;
-        let (count, separation) = transforms
-            .filter_map(|other_transform| {
-                if other_transform
-                    .translation
-                    .distance_squared(transform.translation)
-                    < flock_detection_range_squared
-                {
-                    // close, avoid
-                    Some((transform.translation - other_transform.translation).normalize())
-                } else {
-                    None
-                }
-            })
+    fn calculate_separation(&self, transform: &Transform, boids: &SpatialHash) -> Vec2 {
+        let position = transform.translation.xy();
+        let (count, separation) = boids
+            .within(position, self.flock_detection_range)
+            // close, avoid
+            .map(|other| (position - other).normalize())
             .filter(|vec| !vec.is_nan())
             .fold((0.0f32, Vec2::ZERO), |(count, acc), vec| {
-                (count + 1.0, acc + vec.xy())
+                (count + 1.0, acc + vec)
             });
         if count == 0.0 {
             return Vec2::ZERO;
         }
         separation / count
     }
+
+    #[cfg(test)]
+    pub(crate) fn flock_detection_range(&self) -> f32 {
+        self.flock_detection_range
+    }
+
+    #[cfg(test)]
+    pub(crate) fn current_separation(&self) -> Vec2 {
+        self.current_separation
+    }
+
     #[instrument(skip_all)]
-    fn update_separation<'a>(
-        &mut self,
-        transform: &Transform,
-        transforms: impl Iterator<Item = &'a Transform>,
-    ) {
-        let separation = self.calculate_separation(transform, transforms);
+    fn update_separation(&mut self, transform: &Transform, boids: &SpatialHash) {
+        let separation = self.calculate_separation(transform, boids);
         tracing::trace!(?separation);
         self.current_separation = separation;
     }
@@ -152,43 +183,43 @@ pub enum BoidSeparationUpdateRate {
     Rate(f32),
 }
 
-#[derive(Component, Debug, Default, Clone, Copy)]
-pub enum EnemyClass {
-    // will go towards the player
-    #[default]
-    Melee,
-    // will keep its distance
-    Ranged {
-        max_range: f32,
-    },
-}
-
-fn boids_calculate_separation(
-    mut query: Query<(&Transform, &EnemyClass, &mut Boid), With<Enemy>>,
+/// Throttles the [`SpatialHash`] rebuild along with the separation, the grid is stale until
+/// the next update.
+fn boid_separation_due(
     time: Res<Time>,
     mut tick_time: Local<f32>,
     update_rate: Res<BoidSeparationUpdateRate>,
-) {
+) -> bool {
     match *update_rate {
-        BoidSeparationUpdateRate::PerFrame => {}
+        BoidSeparationUpdateRate::PerFrame => true,
         BoidSeparationUpdateRate::Rate(rate) => {
-            let dt = time.delta_secs();
-            *tick_time += dt;
+            *tick_time += time.delta_secs();
             if *tick_time > rate {
                 *tick_time -= rate;
+                true
             } else {
-                return;
+                false
             }
         }
     }
-    // par iter cause boids are expensive
-    // TODO: spread calculations across frames
-    let enemies = query
-        .iter()
-        .map(|el| (*el.0, *el.1, *el.2))
-        .collect::<Arc<[_]>>();
-    query.par_iter_mut().for_each(|(transform, _, mut boid)| {
-        boid.update_separation(transform, enemies.iter().map(|(transform, _, _)| transform));
+}
+
+pub(crate) fn rebuild_spatial_hash(
+    mut boids: ResMut<SpatialHash>,
+    query: Query<&Transform, With<Enemy>>,
+) {
+    boids.clear();
+    for transform in query.iter() {
+        boids.insert(transform.translation.xy());
+    }
+}
+
+pub(crate) fn boids_calculate_separation(
+    mut query: Query<(&Transform, &mut Boid), With<Enemy>>,
+    boids: Res<SpatialHash>,
+) {
+    query.par_iter_mut().for_each(|(transform, mut boid)| {
+        boid.update_separation(transform, &boids);
     });
 }
 
@@ -199,38 +230,32 @@ fn boids_move_towards_player(
             Entity,
             &mut Velocity,
             &Transform,
-            &EnemyClass,
-            &mut Boid,
+            &mut Behaviour,
+            &Boid,
             &Speed,
         ),
-        With<Enemy>,
+        (With<Enemy>, Without<Dead>),
     >,
     player_transform: Single<&Transform, (With<Player>, Without<Dead>)>,
     time: Res<Time>,
 ) {
     let dt = time.delta_secs();
-    for (enemy, mut velocity, transform, enemy_class, boid, speed) in query.iter_mut() {
+    for (enemy, mut velocity, transform, mut behaviour, boid, speed) in query.iter_mut() {
         // apply separation
         velocity.linvel += dt * boid.current_separation * boid.separation_speed;
         let distance_to_player = player_transform.translation.distance(transform.translation);
-        let mut move_towards_player = || {
-            commands.entity(enemy).try_insert_if_new(Moving);
-            let dir_to_player = (player_transform.translation - transform.translation).normalize();
-            velocity.linvel = velocity
-                .linvel
-                .move_towards(dir_to_player.xy() * speed.0, dt * speed.0 * 2.0);
+        let dir_to_player = (player_transform.translation - transform.translation)
+            .normalize()
+            .xy();
+        let target = behaviour.steer(time.delta(), dir_to_player, distance_to_player, speed.0);
+        let Some(target) = target else {
+            commands.entity(enemy).try_remove::<Moving>();
+            continue;
         };
-        match enemy_class {
-            EnemyClass::Melee => {
-                move_towards_player();
-            }
-            EnemyClass::Ranged { max_range } if distance_to_player > *max_range => {
-                move_towards_player();
-            }
-            EnemyClass::Ranged { .. } => {
-                commands.entity(enemy).try_remove::<Moving>();
-            }
-        }
+        commands.entity(enemy).try_insert_if_new(Moving);
+        // faster steerings, like charges, speed up faster too
+        let acceleration = target.length().max(speed.0) * 2.0;
+        velocity.linvel = velocity.linvel.move_towards(target, dt * acceleration);
     }
 }
 
@@ -288,39 +313,81 @@ fn enemy_check_for_player_collisions(
 }
 
 #[derive(Event, Debug)]
-pub struct EnemyHitEvent(Entity, Transform);
+pub struct EnemyHitEvent {
+    pub enemy: Entity,
+    pub hitbox: Entity,
+    hitbox_transform: Transform,
+}
 
 #[derive(Event, Debug)]
-pub struct EnemyDiedEvent(Entity);
+pub struct EnemyDiedEvent {
+    pub enemy: Entity,
+    /// The hitbox that dealt the killing blow.
+    pub killer: Entity,
+}
+
+/// A hitbox that damages an enemy once per pass, instead of every tick they overlap.
+/// The enemy has to leave the hitbox before it can be damaged by it again.
+#[derive(Component, Debug, Default, Deref)]
+#[require(CollidingEntities)]
+pub struct HitOnce(EntityHashSet);
+
+fn forget_passed_hurtboxes(mut hitboxes: Query<(&mut HitOnce, &CollidingEntities)>) {
+    for (mut hit, colliding_entities) in hitboxes.iter_mut() {
+        hit.0
+            .retain(|hurtbox| colliding_entities.contains(*hurtbox));
+    }
+}
 
 #[instrument(skip_all)]
 fn enemies_take_damage(
-    mut enemies: Query<(Entity, &mut Health, &CollidingEntities), With<EnemyHurtbox>>,
-    hitboxes: Query<(&Damage, &Transform), With<Hitbox>>,
+    mut enemies: Query<
+        (Entity, &mut Health, &CollidingEntities),
+        (With<EnemyHurtbox>, Without<Dead>),
+    >,
+    mut hitboxes: Query<(&Damage, &Transform, Option<&mut HitOnce>), With<Hitbox>>,
     mut hit_events: EventWriter<EnemyHitEvent>,
     mut died_events: EventWriter<EnemyDiedEvent>,
 ) {
     for (enemy, mut health, colliding_entities) in enemies.iter_mut() {
         for hitbox in colliding_entities.iter() {
-            let Ok((damage, transform)) = hitboxes.get(hitbox) else {
+            let Ok((damage, transform, hit)) = hitboxes.get_mut(hitbox) else {
                 continue;
             };
+            if hit.is_some_and(|mut hit| !hit.0.insert(enemy)) {
+                continue;
+            }
 
+            let was_alive = **health > 0;
             **health -= **damage;
-            hit_events.write(EnemyHitEvent(enemy, *transform));
-            if **health <= 0 {
-                died_events.write(EnemyDiedEvent(enemy));
+            hit_events.write(EnemyHitEvent {
+                enemy,
+                hitbox,
+                hitbox_transform: *transform,
+            });
+            // only report the hit that killed it, later overlaps would double count
+            if was_alive && **health <= 0 {
+                died_events.write(EnemyDiedEvent {
+                    enemy,
+                    killer: hitbox,
+                });
             }
         }
     }
 }
 
-fn handle_enemy_died_events(
+pub fn handle_enemy_died_events(
     mut events: EventReader<EnemyDiedEvent>,
     mut commands: Commands,
     mut shake: Single<&mut Shake>,
+    bosses: Query<(), With<Boss>>,
 ) {
-    for EnemyDiedEvent(enemy) in events.read() {
+    for EnemyDiedEvent { enemy, .. } in events.read() {
+        // bosses get a death cinematic, which despawns them once it's over
+        if bosses.contains(*enemy) {
+            commands.entity(*enemy).try_insert(Dead);
+            continue;
+        }
         // TODO: Some effects
         commands.entity(*enemy).try_despawn();
         shake.apply_trauma(0.25);
@@ -328,7 +395,7 @@ fn handle_enemy_died_events(
 }
 
 #[derive(Resource, AssetCollection, Debug)]
-struct EnemyAssets {
+pub struct EnemyAssets {
     #[asset(path = "enemies/hit.particles.ron")]
     hit_particles: Handle<Particle2dEffect>,
     #[asset(path = "enemies/hit_particle.png")]
@@ -344,6 +411,18 @@ struct EnemyAssets {
     hit_sounds: Vec<Handle<AudioSource>>,
 }
 
+#[cfg(test)]
+impl EnemyAssets {
+    /// Handles to nothing, for tests that never look at the particles or play the sounds.
+    pub(crate) fn mock() -> Self {
+        Self {
+            hit_particles: default(),
+            hit_particles_texture: default(),
+            hit_sounds: vec![default()],
+        }
+    }
+}
+
 fn debug_print_enemy_assets(assets: Res<EnemyAssets>) {
     tracing::info!(enemy_assets = ?assets.into_inner());
 }
@@ -366,14 +445,19 @@ fn handle_enemy_hit_events(
     mut events: EventReader<EnemyHitEvent>,
     mut commands: Commands,
     assets: Res<EnemyAssets>,
-    audio: Res<Audio>,
-    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
+    mut rng: ResMut<GameRng>,
     mut hit_sound_idx: Local<usize>,
     mut query: Query<&Transform, With<Enemy>>,
     mut shake: Single<&mut Shake>,
     hit_particles_material: Res<HitParticleMaterial>,
 ) -> Result {
-    for EnemyHitEvent(enemy, hitbox_transform) in events.read() {
+    for EnemyHitEvent {
+        enemy,
+        hitbox_transform,
+        ..
+    } in events.read()
+    {
         let Ok(enemy_transform) = query.get(*enemy) else {
             continue;
         };
@@ -389,9 +473,8 @@ fn handle_enemy_hit_events(
                     .with_rotation(Quat::from_axis_angle(Vec3::Z, from_hitbox.xy().to_angle())),
             );
         shake.apply_trauma(0.1);
-        audio
-            .play(assets.hit_sounds[*hit_sound_idx].clone())
-            .with_volume(volume.calc_sfx(1.0));
+        sfx.play(assets.hit_sounds[*hit_sound_idx].clone())
+            .with_playback_rate(rng.effects().random_range(0.9..1.1));
         *hit_sound_idx = (*hit_sound_idx + 1) % assets.hit_sounds.len();
     }
     Ok(())
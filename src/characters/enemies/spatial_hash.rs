@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::SpatialHash;
+}
+
+/// Side of a [`SpatialHash`] cell, the flock detection range of a default
+/// [`Boid`](super::Boid).
+const BOID_CELL_SIZE: f32 = 20.0;
+
+/// The positions of every boid bucketed in a uniform grid, so that a boid only looks at the
+/// cells around it instead of at every other boid. It's only rebuilt right before the
+/// separation is, so between separation updates it holds where the boids were back then.
+#[derive(Resource, Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    /// Cells are kept once allocated, rebuilding only empties them.
+    cells: HashMap<IVec2, Vec<Vec2>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(BOID_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+    }
+
+    pub fn insert(&mut self, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(position);
+    }
+
+    /// Positions closer than `range` to `position`, including `position` itself if it was
+    /// inserted. The order only depends on the insertion order, so results stay deterministic.
+    pub fn within(&self, position: Vec2, range: f32) -> impl Iterator<Item = Vec2> + '_ {
+        let min = self.cell(position - range);
+        let max = self.cell(position + range);
+        let range_squared = range * range;
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |other| other.distance_squared(position) < range_squared)
+    }
+}
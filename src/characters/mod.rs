@@ -1,7 +1,9 @@
 use std::f32::consts::PI;
+use std::time::Duration;
 
 use crate::autotimer::prelude::*;
 use crate::characters::bullet::bullet_plugin;
+use crate::characters::bullet::patterns::patterns_plugin;
 use crate::characters::enemies::prelude::*;
 use crate::characters::player::{Player, player_plugin};
 use crate::exp_decay::ExpDecay;
@@ -15,14 +17,17 @@ pub mod bullet;
 pub mod enemies;
 pub mod player;
 
+#[cfg(test)]
+mod tests;
+
 pub mod prelude {
     pub use super::Bobbing;
     pub use super::LookAtPlayer;
     pub use super::character_base;
     pub use super::characters_plugin;
     pub use super::{
-        AimDir, Character, Damage, Dashing, Dead, Health, Hitbox, Hurtbox, Iframes, Knockback,
-        Moving, Speed,
+        AimDir, Character, Damage, Dashing, Dead, Health, HitIframes, Hitbox, Hurtbox, Iframes,
+        Knockback, MaxHealth, Moving, Speed,
     };
     pub use super::{
         ENEMY_HITBOX_GROUP, ENEMY_HURTBOX_GROUP, PLAYER_HITBOX_GROUP, PLAYER_HURTBOX_GROUP,
@@ -33,6 +38,7 @@ pub fn characters_plugin(app: &mut App) {
     app.add_event::<ScreenWrapEvent>()
         .add_plugins(player_plugin)
         .add_plugins(bullet_plugin)
+        .add_plugins(patterns_plugin)
         .add_plugins(enemies_plugin)
         .add_systems(
             FixedUpdate,
@@ -82,6 +88,10 @@ pub struct PrevVelocity(Velocity);
 #[derive(Component, Deref, DerefMut, Clone, Copy)]
 pub struct Health(i32);
 
+/// Upper bound for healing a character's [`Health`].
+#[derive(Component, Deref, DerefMut, Clone, Copy)]
+pub struct MaxHealth(i32);
+
 #[derive(Component, Deref, DerefMut, Clone, Copy, Default)]
 pub struct Damage(i32);
 
@@ -266,12 +276,33 @@ fn stop_if_dead(mut commands: Commands, query: Query<Entity, (With<Moving>, Adde
 #[component(on_remove = remove_iframes)]
 pub struct Dashing(AutoTimer<100, TimerOnce>);
 
+/// Invulnerability after getting hit.
+#[derive(Component, Deref, DerefMut)]
+#[require(Iframes)]
+#[component(on_remove = remove_iframes)]
+pub struct HitIframes(Timer);
+
+impl HitIframes {
+    pub fn new(duration: Duration) -> Self {
+        Self(Timer::new(duration, TimerMode::Once))
+    }
+}
+
 #[derive(Component, Default)]
 pub struct Iframes;
 
 // You can also destructure items directly in the signature
 fn remove_iframes(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-    world.commands().entity(entity).remove::<Iframes>();
+    // the removed component is still there while the hook runs,
+    // and the other source of iframes may outlast it
+    world.commands().queue(move |world: &mut World| {
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if !entity.contains::<Dashing>() && !entity.contains::<HitIframes>() {
+            entity.remove::<Iframes>();
+        }
+    });
 }
 
 #[derive(Component, Clone, Copy, Deref, DerefMut)]
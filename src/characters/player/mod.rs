@@ -6,9 +6,11 @@ use tracing::instrument;
 use crate::COLORS;
 use crate::ShakeExt;
 use crate::autotimer::prelude::*;
-use crate::characters::enemies::PlayerHitEvent;
-use crate::characters::player::shoot::PlayerShoot;
+use crate::bindings::prelude::*;
+use crate::characters::bullet::{Bullet, BulletLifetime};
+use crate::characters::enemies::{HitOnce, PlayerHitEvent};
 use crate::characters::player::shoot::player_shoot_plugin;
+use crate::characters::player::shoot::{BoomerangCharges, PlayerShoot};
 use crate::characters::prelude::*;
 use crate::effects::prelude::*;
 use crate::screens::prelude::*;
@@ -25,15 +27,18 @@ use bevy_asset_loader::prelude::*;
 use bevy_enoki::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_trauma_shake::Shake;
+use bon::Builder;
 use leafwing_abilities::prelude::*;
 use leafwing_input_manager::prelude::*;
 
 pub mod shoot;
+pub mod upgrades;
 
 pub mod prelude {
     pub use super::despawn_player;
     pub use super::player_plugin;
     pub use super::spawn_player;
+    pub use super::{ActiveInput, PlayerAbility, PlayerAction, PlayerConfig, PlayerInputSystems};
 }
 
 pub fn player_plugin(app: &mut App) {
@@ -42,10 +47,16 @@ pub fn player_plugin(app: &mut App) {
         .add_plugins(InputManagerPlugin::<PlayerAbility>::default())
         .add_plugins(AbilityPlugin::<PlayerAbility>::default())
         .init_resource::<ActionState<PlayerAction>>()
+        .init_resource::<PlayerConfig>()
         // Defined below, detects whether MKB or gamepad are active
         .add_plugins(InputModeManagerPlugin)
-        .insert_resource(PlayerAction::default_input_map())
+        .insert_resource(PlayerAction::input_map(&InputBindings::default()))
         // Set up the input processing
+        .add_systems(Update, apply_player_bindings)
+        .configure_sets(
+            FixedUpdate,
+            (PlayerInputSystems::Read, PlayerInputSystems::Apply).chain(),
+        )
         .add_systems(
             OnEnter(GameScreen::SplashNext),
             update_boomerang_activation_particles_color,
@@ -53,19 +64,21 @@ pub fn player_plugin(app: &mut App) {
         .add_systems(
             FixedUpdate,
             (
-                control_player,
+                control_player.in_set(PlayerInputSystems::Apply),
                 player_mouse_aim
-                    .before(player_aim)
+                    .in_set(PlayerInputSystems::Read)
                     .run_if(in_state(ActiveInput::MouseKeyboard)),
-                player_aim,
+                player_aim.in_set(PlayerInputSystems::Apply),
                 player_handle_hit_events,
+                player_hit_iframes.after(player_handle_hit_events),
                 player_die_if_out_of_health.after(player_handle_hit_events),
                 on_player_died,
                 player_died_effects
                     .after(player_die_if_out_of_health)
                     .after(on_player_died),
                 player_step_sounds,
-                player_dash_ability,
+                player_dash_ability.in_set(PlayerInputSystems::Apply),
+                player_blood_nova_ability.in_set(PlayerInputSystems::Apply),
                 player_disable_dash_after_timer,
             )
                 .run_if(not(in_state(GameScreen::SplashFirst))),
@@ -83,6 +96,8 @@ pub struct PlayerAssets {
     boomerang_sprite: Handle<Image>,
     #[asset(path = "player/boomerang_activation.particles.ron")]
     boomerang_activation_particles: Handle<Particle2dEffect>,
+    #[asset(path = "player/catch.particles.ron")]
+    catch_particles: Handle<Particle2dEffect>,
     #[asset(
         paths(
             "player/sounds/step_1.wav",
@@ -96,6 +111,25 @@ pub struct PlayerAssets {
     death_sound: Handle<AudioSource>,
     #[asset(path = "player/sounds/shoot.wav")]
     shoot_sound: Handle<AudioSource>,
+    #[asset(path = "player/sounds/catch.wav")]
+    catch_sound: Handle<AudioSource>,
+}
+
+#[cfg(test)]
+impl PlayerAssets {
+    /// Handles to nothing, for tests that never look at the sprites or play the sounds.
+    pub(crate) fn mock() -> Self {
+        Self {
+            sprite: default(),
+            boomerang_sprite: default(),
+            boomerang_activation_particles: default(),
+            catch_particles: default(),
+            step_sounds: vec![default()],
+            death_sound: default(),
+            shoot_sound: default(),
+            catch_sound: default(),
+        }
+    }
 }
 
 fn update_boomerang_activation_particles_color(
@@ -110,6 +144,38 @@ fn update_boomerang_activation_particles_color(
 #[derive(Component, Default)]
 pub struct Player;
 
+/// Tuning of the player, applied when it spawns.
+#[derive(Resource, Debug, Clone, Builder)]
+pub struct PlayerConfig {
+    pub max_health: i32,
+    /// How long the player can't be hit again after a hit.
+    pub hit_iframes: Duration,
+    /// Chance for a killed enemy to drop a heart, `0.0` turns pickups off.
+    pub heart_drop_chance: f64,
+    /// Boomerangs that can be in flight at once.
+    pub max_charges: u32,
+    /// Time for a spent charge to come back without catching the boomerang, much longer than
+    /// a throw takes to come back so that catches matter.
+    pub charge_recharge: Duration,
+    /// [`SpeedMod`](crate::characters::SpeedMod) granted by catching a boomerang that wrapped.
+    pub perfect_catch_speed: f32,
+    pub perfect_catch_duration: Duration,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        PlayerConfig::builder()
+            .max_health(3)
+            .hit_iframes(Duration::from_millis(1200))
+            .heart_drop_chance(0.04)
+            .max_charges(16)
+            .charge_recharge(Duration::from_secs(2))
+            .perfect_catch_speed(1.3)
+            .perfect_catch_duration(Duration::from_millis(1500))
+            .build()
+    }
+}
+
 #[derive(Component, Debug)]
 #[require(Hitbox)]
 pub struct PlayerHitbox;
@@ -118,14 +184,20 @@ pub struct PlayerHitbox;
 #[require(Hurtbox)]
 pub struct PlayerHurtbox;
 
-pub fn spawn_player(mut commands: Commands, player_assets: Res<PlayerAssets>) {
+pub fn spawn_player(
+    mut commands: Commands,
+    player_assets: Res<PlayerAssets>,
+    bindings: Single<&InputBindings>,
+    config: Res<PlayerConfig>,
+) {
     commands
         .spawn(character_base())
         .insert(CollisionGroups::new(
             PLAYER_HURTBOX_GROUP,
             ENEMY_HITBOX_GROUP,
         ))
-        .insert(Health(1))
+        .insert(Health(config.max_health))
+        .insert(MaxHealth(config.max_health))
         .insert(ColliderDebugColor(Hsla::hsl(210.0, 1.0, 0.8)))
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(PlayerHurtbox)
@@ -134,9 +206,13 @@ pub fn spawn_player(mut commands: Commands, player_assets: Res<PlayerAssets>) {
             rate: 0.025,
             spread: 5.0_f32.to_radians(),
         })
+        .insert(BoomerangCharges::new(
+            config.max_charges,
+            config.charge_recharge,
+        ))
         .insert(Speed(96.0))
         .insert(Bobbing)
-        .insert(PlayerAbility::input_map())
+        .insert(PlayerAbility::input_map(&bindings))
         .insert(PlayerAbility::cooldowns())
         .insert(Sprite {
             anchor: bevy::sprite::Anchor::BottomCenter,
@@ -149,9 +225,19 @@ pub fn despawn_player(mut commands: Commands, player: Single<Entity, With<Player
     commands.entity(*player).try_despawn();
 }
 
+/// Splits reading the player's input from acting on it in `FixedUpdate`,
+/// so the input can be recorded or replaced in between.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerInputSystems {
+    /// Fills the player's `ActionState`s beyond what leafwing does, e.g. mouse aim.
+    Read,
+    /// Moves, aims, shoots and dashes from the `ActionState`s.
+    Apply,
+}
+
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
 #[actionlike(DualAxis)]
-enum PlayerAction {
+pub enum PlayerAction {
     Move,
     Aim,
     #[actionlike(Button)]
@@ -159,21 +245,20 @@ enum PlayerAction {
 }
 
 impl PlayerAction {
-    /// Define the default bindings to the input
-    fn default_input_map() -> InputMap<Self> {
+    /// Define the bindings to the input, shooting follows the player's [`InputBindings`]
+    fn input_map(bindings: &InputBindings) -> InputMap<Self> {
         let mut input_map = InputMap::default();
 
-        // Default gamepad input bindings
+        // Gamepad input bindings
         input_map.insert_dual_axis(Self::Move, GamepadStick::LEFT);
         input_map.insert_dual_axis(Self::Move, VirtualDPad::dpad());
         input_map.insert_dual_axis(Self::Aim, GamepadStick::RIGHT);
-        input_map.insert(Self::Shoot, GamepadButton::RightTrigger);
 
-        // Default kbm input bindings
+        // Kbm input bindings
         input_map.insert_dual_axis(Self::Move, VirtualDPad::wasd());
         input_map.insert_dual_axis(Self::Aim, MouseMove::default());
-        input_map.insert(Self::Shoot, MouseButton::Left);
 
+        bindings.insert_into(&mut input_map, BindableAction::Shoot, Self::Shoot);
         input_map
     }
 }
@@ -181,14 +266,29 @@ impl PlayerAction {
 #[derive(
     Actionlike, Abilitylike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect, strum::EnumIter,
 )]
-enum PlayerAbility {
+pub enum PlayerAbility {
     Dash,
+    /// Pulls every boomerang in flight back to the player, armed.
+    Recall,
+    /// Spends health to hurt every enemy around the player.
+    BloodNova,
 }
 
 impl PlayerAbility {
+    /// Short name shown on the HUD.
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerAbility::Dash => "dash",
+            PlayerAbility::Recall => "recall",
+            PlayerAbility::BloodNova => "nova",
+        }
+    }
+
     fn cooldown(&self) -> Cooldown {
         match self {
             PlayerAbility::Dash => Cooldown::from_secs(0.5),
+            PlayerAbility::Recall => Cooldown::from_secs(3.0),
+            PlayerAbility::BloodNova => Cooldown::from_secs(6.0),
         }
     }
 
@@ -200,10 +300,22 @@ impl PlayerAbility {
         cooldowns
     }
 
-    fn input_map() -> InputMap<Self> {
-        InputMap::default()
-            .with(Self::Dash, KeyCode::Space)
-            .with(Self::Dash, GamepadButton::LeftTrigger2)
+    fn input_map(bindings: &InputBindings) -> InputMap<Self> {
+        let mut input_map = bindings.input_map(BindableAction::Dash, Self::Dash);
+        bindings.insert_into(&mut input_map, BindableAction::Recall, Self::Recall);
+        bindings.insert_into(&mut input_map, BindableAction::Nova, Self::BloodNova);
+        input_map
+    }
+}
+
+fn apply_player_bindings(
+    bindings: Single<&InputBindings, Changed<InputBindings>>,
+    mut actions: ResMut<InputMap<PlayerAction>>,
+    mut abilities: Query<&mut InputMap<PlayerAbility>, With<Player>>,
+) {
+    *actions = PlayerAction::input_map(&bindings);
+    for mut input_map in abilities.iter_mut() {
+        *input_map = PlayerAbility::input_map(&bindings);
     }
 }
 
@@ -224,7 +336,7 @@ impl Plugin for InputModeManagerPlugin {
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
-enum ActiveInput {
+pub enum ActiveInput {
     #[default]
     MouseKeyboard,
     Gamepad,
@@ -336,14 +448,24 @@ fn player_handle_hit_events(
     player: Single<(Entity, &mut Health, &Transform), With<Player>>,
     mut event_reader: EventReader<PlayerHitEvent>,
     mut shake: Single<&mut Shake>,
+    config: Res<PlayerConfig>,
 ) {
     let (player, mut health, transform) = player.into_inner();
+    let mut hit = false;
     for PlayerHitEvent {
         damage,
         source_transform,
     } in event_reader.read()
     {
+        // hits landing on the same tick are covered by the iframes of the first one
+        if hit {
+            continue;
+        }
+        hit = true;
         **health -= **damage;
+        commands
+            .entity(player)
+            .insert(HitIframes::new(config.hit_iframes));
         if let Some(source_transform) = source_transform {
             let from_source = (transform.translation - source_transform.translation)
                 .normalize()
@@ -356,6 +478,23 @@ fn player_handle_hit_events(
     }
 }
 
+/// Flashes the player's sprite until its [`HitIframes`] run out.
+fn player_hit_iframes(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Single<(Entity, &mut HitIframes, Option<&mut Sprite>), With<Player>>,
+) {
+    let (player, mut iframes, sprite) = player.into_inner();
+    iframes.tick(time.delta());
+    let visible = iframes.finished() || (iframes.elapsed_secs() / 0.08) as u32 % 2 == 1;
+    if let Some(mut sprite) = sprite {
+        sprite.color.set_alpha(if visible { 1.0 } else { 0.2 });
+    }
+    if iframes.finished() {
+        commands.entity(player).try_remove::<HitIframes>();
+    }
+}
+
 fn player_die_if_out_of_health(
     mut commands: Commands,
     player: Single<(Entity, &Health), With<Player>>,
@@ -372,16 +511,18 @@ pub struct PlayerDeathEffectsTimer(AutoTimer<800, TimerOnce>);
 fn on_player_died(
     mut commands: Commands,
     player: Single<Entity, (With<Player>, Added<Dead>)>,
-    audio: Res<Audio>,
-    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
     assets: Res<PlayerAssets>,
+    mut duck: EventWriter<DuckMusic>,
 ) {
     commands
         .entity(*player)
         .insert(PlayerDeathEffectsTimer::default());
-    audio
-        .play(assets.death_sound.clone())
-        .with_volume(volume.calc_sfx(1.0));
+    sfx.play(assets.death_sound.clone());
+    duck.write(DuckMusic {
+        volume: 0.2,
+        duration: Duration::from_secs(2),
+    });
 }
 
 fn player_died_effects(
@@ -399,8 +540,7 @@ fn player_died_effects(
 
 fn player_step_sounds(
     _: Single<(), (With<Player>, With<Moving>)>,
-    audio: Res<Audio>,
-    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
     assets: Res<PlayerAssets>,
     mut step_timer: Local<AutoTimer<250, TimerRepeating>>,
     time: Res<Time>,
@@ -408,9 +548,8 @@ fn player_step_sounds(
 ) {
     step_timer.tick(time.delta());
     if step_timer.just_finished() {
-        audio
-            .play(assets.step_sounds[*step_idx].clone())
-            .with_volume(volume.calc_sfx(0.3));
+        sfx.play(assets.step_sounds[*step_idx].clone())
+            .with_volume(0.3);
         *step_idx = (*step_idx + 1) % assets.step_sounds.len();
     }
 }
@@ -428,6 +567,7 @@ fn player_dash_ability(
         (With<Player>, With<Moving>, Without<Dead>),
     >,
     mut shake: Single<&mut Shake>,
+    mut duck: EventWriter<DuckMusic>,
 ) -> Result {
     let (player, mut velocity, actions, mut cooldowns) = query.into_inner();
     if actions.just_pressed(&PlayerAbility::Dash) {
@@ -443,12 +583,70 @@ fn player_dash_ability(
             );
             velocity.linvel = velocity.linvel.normalize_or_zero() * 600.0;
             shake.apply_trauma(0.2);
+            duck.write(DuckMusic {
+                volume: 0.6,
+                duration: Duration::from_millis(300),
+            });
             tracing::info!(?player, "dashed");
         }
     }
     Ok(())
 }
 
+/// Radius of the blood nova around the player.
+const BLOOD_NOVA_RADIUS: f32 = 40.0;
+const BLOOD_NOVA_DAMAGE: i32 = 3;
+/// Health spent on a blood nova, it can't be cast without health to spare.
+const BLOOD_NOVA_COST: i32 = 1;
+
+#[instrument(skip_all)]
+fn player_blood_nova_ability(
+    mut commands: Commands,
+    query: Single<
+        (
+            Entity,
+            &Transform,
+            &mut Health,
+            &ActionState<PlayerAbility>,
+            &mut CooldownState<PlayerAbility>,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+    mut shake: Single<&mut Shake>,
+) {
+    let (player, transform, mut health, actions, mut cooldowns) = query.into_inner();
+    if !actions.just_pressed(&PlayerAbility::BloodNova) || **health <= BLOOD_NOVA_COST {
+        return;
+    }
+    if cooldowns.trigger(&PlayerAbility::BloodNova).is_err() {
+        return;
+    }
+    **health -= BLOOD_NOVA_COST;
+    // a single tick long hitbox, `HitOnce` keeps it to one hit per enemy
+    commands.spawn((
+        Bullet,
+        BulletLifetime(0.05),
+        Transform::from_translation(transform.translation),
+        Collider::ball(BLOOD_NOVA_RADIUS),
+        Sensor,
+        ActiveCollisionTypes::all(),
+        CollisionGroups::new(PLAYER_HITBOX_GROUP, ENEMY_HURTBOX_GROUP),
+        PlayerHitbox,
+        HitOnce::default(),
+        Damage(BLOOD_NOVA_DAMAGE),
+    ));
+    // a dash trail that's still running keeps going instead of being cut short
+    commands.entity(player).insert_if_new(
+        GhostSpriteSpawner::builder()
+            .kind(GhostSpriteSpawnerKind::Count(6))
+            .rate(0.03)
+            .ghost_decay(6.0)
+            .build(),
+    );
+    shake.apply_trauma(0.4);
+    tracing::info!(?player, "blood nova");
+}
+
 fn player_disable_dash_after_timer(
     mut commands: Commands,
     time: Res<Time>,
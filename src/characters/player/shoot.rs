@@ -1,8 +1,10 @@
 use std::f32::consts::PI;
+use std::time::Duration;
 
 use crate::ShakeExt;
 use crate::autotimer::prelude::*;
 use crate::effects::prelude::*;
+use crate::rng::prelude::*;
 use crate::{audio::prelude::*, exp_decay::ExpDecay};
 use bevy::{
     prelude::*,
@@ -12,17 +14,22 @@ use bevy::{
 use bevy_enoki::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_trauma_shake::Shake;
+use leafwing_abilities::prelude::*;
 use leafwing_input_manager::prelude::ActionState;
 use rand::Rng;
 use tracing::instrument;
 
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::upgrades::{BASE_THROW_SPEED, BoomerangHits, BoomerangModifiers};
 use crate::characters::{player::PlayerHitbox, prelude::*};
 use crate::{
     COLORS,
     characters::{
-        AimDir, PLAYER_HITBOX_GROUP,
+        AimDir, PLAYER_HITBOX_GROUP, SpeedMod,
         bullet::{BulletMaxWrap, BulletWrapCount, bullet_base},
-        player::{Player, PlayerAction, PlayerAssets},
+        player::{
+            Player, PlayerAbility, PlayerAction, PlayerAssets, PlayerConfig, PlayerInputSystems,
+        },
     },
     screens::GameScreen,
 };
@@ -38,7 +45,10 @@ pub fn player_shoot_plugin(app: &mut App) {
         .add_systems(
             FixedUpdate,
             (
-                player_shoot_system,
+                recharge_boomerangs.before(PlayerInputSystems::Apply),
+                player_shoot_system.in_set(PlayerInputSystems::Apply),
+                player_recall_ability.in_set(PlayerInputSystems::Apply),
+                perfect_catch_buff_expire,
                 boomerang_activate_after_wrap,
                 boomerang_activate_effects.after(boomerang_fly),
                 boomerang_material_update,
@@ -50,11 +60,11 @@ pub fn player_shoot_plugin(app: &mut App) {
                 .after(setup_boomerang_mesh),
         )
         .add_systems(
-            PostUpdate,
-            (
-                boomerang_destroy_on_contact,
-                boomerang_destroy_close_to_player,
-            )
+            FixedPostUpdate,
+            // boomerangs destroyed by a hit are despawned by the sync point the chain adds
+            // before catches, so they're never caught on the tick they break
+            (boomerang_handle_hits, boomerang_catch)
+                .chain()
                 .run_if(not(in_state(GameScreen::SplashFirst))),
         );
 }
@@ -65,19 +75,52 @@ pub struct PlayerShoot {
     pub spread: f32,
 }
 
+/// Limits the boomerangs in flight, every thrown boomerang spends a charge.
+/// Catching a boomerang refunds one, otherwise they slowly come back on their own.
+#[derive(Component, Debug, Clone)]
+pub struct BoomerangCharges {
+    pub current: u32,
+    pub max: u32,
+    recharge: Timer,
+}
+
+impl BoomerangCharges {
+    pub fn new(max: u32, recharge: Duration) -> Self {
+        Self {
+            current: max,
+            max,
+            recharge: Timer::new(recharge, TimerMode::Repeating),
+        }
+    }
+
+    fn refund(&mut self) {
+        self.current = (self.current + 1).min(self.max);
+    }
+}
+
+/// Runs out the [`SpeedMod`] of a perfect catch, catching another one starts it over.
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct PerfectCatchBuff(Timer);
+
 #[derive(Component, Debug, Clone, Copy, Default)]
 pub struct PlayerBoomerang {
     traveled: f32,
     max_distance: f32,
+    /// Dealt once the boomerang is armed.
+    damage: i32,
+    /// Multiplier of the spin, set from the player's [`Spin`](super::upgrades::Spin) when thrown.
+    spin: f32,
 }
 
 #[derive(Component, Debug, Clone, Copy, Default)]
 struct PlayerBoomerangGhostSprite;
 
 impl PlayerBoomerang {
-    fn new(max_distance: f32) -> Self {
+    pub fn new(max_distance: f32) -> Self {
         Self {
             max_distance,
+            damage: 1,
+            spin: 1.0,
             ..default()
         }
     }
@@ -95,82 +138,147 @@ fn player_shoot_system(
     time: Res<Time>,
     mut materials: ResMut<Assets<PlayerBoomerangMaterial>>,
     meshes: Res<BoomerangMesh>,
-    player: Single<(&Transform, &AimDir, &PlayerShoot), With<Player>>,
+    player: Single<
+        (
+            &Transform,
+            &AimDir,
+            &PlayerShoot,
+            BoomerangModifiers,
+            Option<&mut BoomerangCharges>,
+        ),
+        With<Player>,
+    >,
     action_state: Res<ActionState<PlayerAction>>,
     mut cooldown: Local<f32>,
     player_assets: Res<PlayerAssets>,
-    audio: Res<Audio>,
-    volume: Res<VolumeSettings>,
+    sfx: Res<AudioChannel<Sfx>>,
+    mut rng: ResMut<GameRng>,
     mut shoot_timer: Local<AutoTimer<100, TimerRepeating>>,
     mut shake: Single<&mut Shake>,
 ) {
     let dt = time.delta_secs();
     let mesh_handle = meshes.clone();
-    let (transform, &aim_dir, &PlayerShoot { rate, spread }) = player.into_inner();
+    let (transform, &aim_dir, &PlayerShoot { rate, spread }, modifiers, mut charges) =
+        player.into_inner();
     if action_state.pressed(&PlayerAction::Shoot) {
         *cooldown -= dt;
         shoot_timer.tick(time.delta());
         if shoot_timer.just_finished() {
-            audio
-                .play(player_assets.shoot_sound.clone())
-                .with_volume(volume.calc_sfx(1.0));
+            sfx.play(player_assets.shoot_sound.clone());
         }
-        if *cooldown <= 0.0 {
+        let charged = charges.as_ref().is_none_or(|charges| charges.current > 0);
+        if *cooldown <= 0.0 && charged {
             *cooldown += rate;
             // shoot
             let half_spread = spread / 2.0;
-            let angle = rand::rng().random_range(-half_spread..half_spread);
-            let material = materials.add(PlayerBoomerangMaterial {
-                color_amount: LinearRgba::new(0., 0., 0., 0.),
-                color: COLORS[2].into(),
-                disabled_color: COLORS[4].with_alpha(0.8).into(),
-                base_sampler: player_assets.boomerang_sprite.clone(),
-            });
-            let direction = Vec2::from_angle(aim_dir.to_angle() + angle);
+            let angle = rng.weapons().random_range(-half_spread..half_spread);
             shake.apply_trauma(0.1);
-            commands
-                .spawn(bullet_base(2.0))
-                // .remove::<BulletLifetime>()
-                .insert(BulletMaxWrap(1))
-                .insert(PlayerBoomerang::new(64.))
-                .insert(Mesh2d(mesh_handle))
-                .insert(BoomerangMaterialId(material.id()))
-                .insert(MeshMaterial2d(material))
-                .insert(Collider::ball(4.0))
-                .insert(Sensor)
-                .insert(CollidingEntities::default())
-                .insert(CollisionGroups::new(
-                    PLAYER_HITBOX_GROUP,
-                    ENEMY_HURTBOX_GROUP,
-                ))
-                .insert(PlayerHitbox)
-                .insert(Transform::from_translation(
-                    transform.translation + aim_dir.extend(0.0) * 8.0 + vec3(0.0, 8.0, 4.0),
-                ))
-                .insert(Velocity {
-                    linvel: direction * 200.0,
-                    ..default()
+            for offset in modifiers.throw_angles() {
+                // multishot throws as many boomerangs as there are charges left
+                if let Some(charges) = charges.as_mut() {
+                    if charges.current == 0 {
+                        break;
+                    }
+                    charges.current -= 1;
+                }
+                let material = materials.add(PlayerBoomerangMaterial {
+                    color_amount: LinearRgba::new(0., 0., 0., 0.),
+                    color: COLORS[2].into(),
+                    disabled_color: COLORS[4].with_alpha(0.8).into(),
+                    base_sampler: player_assets.boomerang_sprite.clone(),
                 });
+                let direction = Vec2::from_angle(aim_dir.to_angle() + angle + offset);
+                commands
+                    .spawn(bullet_base(2.0))
+                    // .remove::<BulletLifetime>()
+                    .insert(BulletMaxWrap(modifiers.max_wraps()))
+                    .insert(PlayerBoomerang {
+                        damage: modifiers.damage(),
+                        spin: modifiers.spin(),
+                        ..PlayerBoomerang::new(modifiers.max_distance())
+                    })
+                    .insert(Mesh2d(mesh_handle.clone()))
+                    .insert(BoomerangMaterialId(material.id()))
+                    .insert(MeshMaterial2d(material))
+                    .insert(Collider::ball(4.0))
+                    .insert(Sensor)
+                    .insert(HitOnce::default())
+                    .insert(modifiers.hits())
+                    .insert(CollisionGroups::new(
+                        PLAYER_HITBOX_GROUP,
+                        ENEMY_HURTBOX_GROUP,
+                    ))
+                    .insert(PlayerHitbox)
+                    .insert(Transform::from_translation(
+                        transform.translation + aim_dir.extend(0.0) * 8.0 + vec3(0.0, 8.0, 4.0),
+                    ))
+                    .insert(Velocity {
+                        linvel: direction * BASE_THROW_SPEED * modifiers.spin(),
+                        ..default()
+                    });
+            }
         }
     } else {
         *cooldown = 0.0;
     }
 }
 
-fn spin_boomerangs(mut query: Query<&mut Transform, With<PlayerBoomerang>>, time: Res<Time>) {
+#[instrument(skip_all)]
+fn player_recall_ability(
+    mut commands: Commands,
+    player: Single<
+        (
+            Entity,
+            &ActionState<PlayerAbility>,
+            &mut CooldownState<PlayerAbility>,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+    mut boomerangs: Query<&mut PlayerBoomerang>,
+    mut shake: Single<&mut Shake>,
+) {
+    let (player, actions, mut cooldowns) = player.into_inner();
+    if !actions.just_pressed(&PlayerAbility::Recall) || boomerangs.is_empty() {
+        return;
+    }
+    if cooldowns.trigger(&PlayerAbility::Recall).is_err() {
+        return;
+    }
+    // past their distance, `boomerang_fly` turns them around and arms them
+    for mut boomerang in boomerangs.iter_mut() {
+        boomerang.traveled = boomerang.traveled.max(boomerang.max_distance) + 1.0;
+    }
+    // keeps a running dash trail, see `player_blood_nova_ability`
+    commands.entity(player).insert_if_new(
+        GhostSpriteSpawner::builder()
+            .kind(GhostSpriteSpawnerKind::Count(4))
+            .rate(0.05)
+            .ghost_decay(8.0)
+            .build(),
+    );
+    shake.apply_trauma(0.15);
+    tracing::info!(?player, "recalled boomerangs");
+}
+
+fn spin_boomerangs(mut query: Query<(&mut Transform, &PlayerBoomerang)>, time: Res<Time>) {
     let dt = time.delta_secs();
-    for mut transform in query.iter_mut() {
-        transform.rotate(Quat::from_axis_angle(Vec3::Z, PI * 2.0 * dt * 8.0))
+    for (mut transform, boomerang) in query.iter_mut() {
+        transform.rotate(Quat::from_axis_angle(
+            Vec3::Z,
+            PI * 2.0 * dt * 8.0 * boomerang.spin,
+        ))
     }
 }
 
 fn boomerang_activate_after_wrap(
-    query: Query<(Entity, &BulletWrapCount), With<PlayerBoomerang>>,
+    query: Query<(Entity, &BulletWrapCount, &PlayerBoomerang)>,
     mut commands: Commands,
 ) {
-    for (boomerang, wrap_count) in query.iter() {
+    for (entity, wrap_count, boomerang) in query.iter() {
         if **wrap_count > 0 {
-            commands.entity(boomerang).insert_if_new(Damage(1));
+            commands
+                .entity(entity)
+                .insert_if_new(Damage(boomerang.damage));
         }
     }
 }
@@ -192,7 +300,9 @@ fn boomerang_fly(
                 let to_player = (player_transform.translation - transform.translation).normalize();
                 velocity.linvel = to_player.xy() * speed;
                 commands.command_scope(|mut commands| {
-                    commands.entity(boomerang_id).insert_if_new(Damage(1));
+                    commands
+                        .entity(boomerang_id)
+                        .insert_if_new(Damage(boomerang.damage));
                 })
             }
         });
@@ -221,43 +331,141 @@ fn boomerang_activate_effects(
     }
 }
 
-fn boomerang_destroy_close_to_player(
+fn recharge_boomerangs(
+    time: Res<Time>,
+    mut charges: Single<&mut BoomerangCharges, (With<Player>, Without<Dead>)>,
+) {
+    if charges.current >= charges.max {
+        charges.recharge.reset();
+        return;
+    }
+    charges.recharge.tick(time.delta());
+    if charges.recharge.just_finished() {
+        charges.refund();
+    }
+}
+
+/// Catches returning boomerangs that reach the player, a boomerang that wrapped
+/// around the screen on its way makes a perfect catch.
+fn boomerang_catch(
     mut commands: Commands,
-    mut query: Query<(
+    query: Query<(
         Entity,
-        &mut PlayerBoomerang,
+        &PlayerBoomerang,
         &Transform,
+        &BulletWrapCount,
         Option<&GhostSpriteSpawnerGeneric>,
     )>,
-    player_transform: Single<&Transform, With<Player>>,
+    player: Single<(Entity, &Transform, Option<&mut BoomerangCharges>), With<Player>>,
+    config: Res<PlayerConfig>,
+    assets: Res<PlayerAssets>,
+    sfx: Res<AudioChannel<Sfx>>,
 ) {
-    for (boomerang_id, boomerang, transform, spawner) in query.iter_mut() {
-        if boomerang.traveled > boomerang.max_distance
+    let (player, player_transform, mut charges) = player.into_inner();
+    for (boomerang_id, boomerang, transform, wrap_count, spawner) in query.iter() {
+        let caught = boomerang.traveled > boomerang.max_distance
             && transform
                 .translation
                 .distance_squared(player_transform.translation)
-                < 256.0
-        {
-            commands.entity(boomerang_id).despawn();
-            if let Some(spawner) = spawner {
-                commands.spawn(spawner.clone());
-            }
+                < 256.0;
+        if !caught {
+            continue;
+        }
+        commands.entity(boomerang_id).try_despawn();
+        if let Some(spawner) = spawner {
+            commands.spawn(spawner.clone());
+        }
+        if let Some(charges) = charges.as_mut() {
+            charges.refund();
         }
+        commands.spawn((
+            ParticleSpawner::default(),
+            ParticleEffectHandle(assets.catch_particles.clone()),
+            Transform::from_translation(transform.translation.with_z(10.0)),
+            OneShot::Despawn,
+        ));
+        sfx.play(assets.catch_sound.clone());
+        if **wrap_count > 0 {
+            tracing::debug!(?player, "perfect catch");
+            commands.entity(player).try_insert((
+                SpeedMod(config.perfect_catch_speed),
+                PerfectCatchBuff(Timer::new(config.perfect_catch_duration, TimerMode::Once)),
+            ));
+        }
+    }
+}
+
+fn perfect_catch_buff_expire(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Single<(Entity, &mut PerfectCatchBuff), With<Player>>,
+) {
+    let (player, mut buff) = player.into_inner();
+    buff.tick(time.delta());
+    if buff.finished() {
+        commands
+            .entity(player)
+            .try_remove::<(PerfectCatchBuff, SpeedMod)>();
     }
 }
 
 #[instrument(skip_all)]
-fn boomerang_destroy_on_contact(
-    mut enemies: Query<(Entity, &CollidingEntities), (With<PlayerBoomerang>, With<Damage>)>,
-    mut spawners: Query<&GhostSpriteSpawnerGeneric, With<PlayerBoomerang>>,
+fn boomerang_handle_hits(
     mut commands: Commands,
+    mut events: EventReader<EnemyHitEvent>,
+    mut boomerangs: Query<
+        (
+            &mut PlayerBoomerang,
+            &mut BoomerangHits,
+            &mut Velocity,
+            &Transform,
+            &HitOnce,
+            Option<&GhostSpriteSpawnerGeneric>,
+        ),
+        With<Damage>,
+    >,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
 ) {
-    for (boomerang, colliding_entities) in enemies.iter_mut() {
-        if !colliding_entities.is_empty() {
-            commands.entity(boomerang).try_despawn();
-            if let Ok(spawner) = spawners.get(boomerang) {
-                commands.spawn(spawner.clone());
+    let mut destroyed = Vec::new();
+    for EnemyHitEvent { enemy, hitbox, .. } in events.read() {
+        if destroyed.contains(hitbox) {
+            continue;
+        }
+        let Ok((mut boomerang, mut hits, mut velocity, transform, hit, spawner)) =
+            boomerangs.get_mut(*hitbox)
+        else {
+            continue;
+        };
+        if hits.persist {
+            continue;
+        }
+        if hits.ricochet > 0 {
+            hits.ricochet -= 1;
+            let position = transform.translation.xy();
+            let target = enemies
+                .iter()
+                .filter(|(other, _)| other != enemy && !hit.contains(other))
+                .map(|(_, other)| other.translation.xy())
+                .min_by(|a, b| {
+                    a.distance_squared(position)
+                        .total_cmp(&b.distance_squared(position))
+                });
+            if let Some(target) = target {
+                // fly out again, the boomerang comes back once it covered its distance
+                velocity.linvel =
+                    (target - position).normalize_or_zero() * velocity.linvel.length();
+                boomerang.traveled = 0.0;
             }
+            continue;
+        }
+        if hits.pierce > 0 {
+            hits.pierce -= 1;
+            continue;
+        }
+        destroyed.push(*hitbox);
+        commands.entity(*hitbox).try_despawn();
+        if let Some(spawner) = spawner {
+            commands.spawn(spawner.clone());
         }
     }
 }
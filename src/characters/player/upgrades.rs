@@ -0,0 +1,199 @@
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+pub mod prelude {
+    pub use super::{
+        BoomerangHits, BoomerangModifiers, ExtraDamage, ExtraWraps, Multishot, Spin, ThrowDistance,
+        Upgrade,
+    };
+}
+
+/// Throw distance of a boomerang without upgrades.
+pub const BASE_THROW_DISTANCE: f32 = 64.0;
+/// Speed of a boomerang without upgrades, in pixels per second.
+pub const BASE_THROW_SPEED: f32 = 200.0;
+/// Screen wraps a boomerang survives without upgrades.
+pub const BASE_MAX_WRAPS: usize = 1;
+
+const DISTANCE_PER_STACK: f32 = 24.0;
+const SPIN_PER_STACK: f32 = 0.25;
+/// Angle between two boomerangs of the same throw.
+const MULTISHOT_ANGLE: f32 = 0.25;
+
+/// A modifier picked from an upgrade draft, every pick stacks one more onto the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum Upgrade {
+    Distance,
+    Wraps,
+    Spin,
+    Damage,
+    Multishot,
+    Pierce,
+    Ricochet,
+    Persist,
+}
+
+impl Upgrade {
+    pub fn name(self) -> &'static str {
+        match self {
+            Upgrade::Distance => "long throw",
+            Upgrade::Wraps => "loop",
+            Upgrade::Spin => "spin",
+            Upgrade::Damage => "edge",
+            Upgrade::Multishot => "split",
+            Upgrade::Pierce => "pierce",
+            Upgrade::Ricochet => "ricochet",
+            Upgrade::Persist => "phantom",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Upgrade::Distance => "boomerangs fly further",
+            Upgrade::Wraps => "one more wrap around the screen",
+            Upgrade::Spin => "boomerangs spin and fly faster",
+            Upgrade::Damage => "+1 damage",
+            Upgrade::Multishot => "one more boomerang per throw",
+            Upgrade::Pierce => "boomerangs pass through one more enemy",
+            Upgrade::Ricochet => "boomerangs bounce to the nearest enemy once more",
+            Upgrade::Persist => "boomerangs fly through everything until caught",
+        }
+    }
+
+    /// Whether picking the upgrade again would change anything.
+    pub fn available(self, modifiers: &BoomerangModifiersItem) -> bool {
+        match self {
+            Upgrade::Persist => !modifiers.hits().persist,
+            _ => true,
+        }
+    }
+
+    /// Stacks the modifier onto `player`.
+    pub fn apply(self, player: &mut EntityCommands) {
+        match self {
+            Upgrade::Distance => {
+                player
+                    .entry::<ThrowDistance>()
+                    .or_default()
+                    .and_modify(|mut distance| **distance += DISTANCE_PER_STACK);
+            }
+            Upgrade::Wraps => {
+                player
+                    .entry::<ExtraWraps>()
+                    .or_default()
+                    .and_modify(|mut wraps| **wraps += 1);
+            }
+            Upgrade::Spin => {
+                player
+                    .entry::<Spin>()
+                    .or_default()
+                    .and_modify(|mut spin| **spin += SPIN_PER_STACK);
+            }
+            Upgrade::Damage => {
+                player
+                    .entry::<ExtraDamage>()
+                    .or_default()
+                    .and_modify(|mut damage| **damage += 1);
+            }
+            Upgrade::Multishot => {
+                player
+                    .entry::<Multishot>()
+                    .or_default()
+                    .and_modify(|mut count| **count += 1);
+            }
+            Upgrade::Pierce => {
+                player
+                    .entry::<BoomerangHits>()
+                    .or_default()
+                    .and_modify(|mut hits| hits.pierce += 1);
+            }
+            Upgrade::Ricochet => {
+                player
+                    .entry::<BoomerangHits>()
+                    .or_default()
+                    .and_modify(|mut hits| hits.ricochet += 1);
+            }
+            Upgrade::Persist => {
+                player
+                    .entry::<BoomerangHits>()
+                    .or_default()
+                    .and_modify(|mut hits| hits.persist = true);
+            }
+        }
+    }
+}
+
+/// Distance added to every throw.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct ThrowDistance(pub f32);
+
+/// Screen wraps added to every boomerang's `BulletMaxWrap`.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct ExtraWraps(pub usize);
+
+/// Added to the spin and speed multiplier of every boomerang, which starts at `1.0`.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct Spin(pub f32);
+
+/// Damage added to every boomerang.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct ExtraDamage(pub i32);
+
+/// Boomerangs added to every throw, fanned out around the aim.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct Multishot(pub usize);
+
+/// What an armed boomerang does when it hits an enemy, it's destroyed once nothing is left.
+///
+/// On the player, the behaviour every thrown boomerang starts with.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct BoomerangHits {
+    /// Enemies passed through.
+    pub pierce: usize,
+    /// Turns toward the nearest enemy that wasn't hit yet, used up before `pierce`.
+    pub ricochet: usize,
+    /// Never destroyed by hits, only by being caught.
+    pub persist: bool,
+}
+
+/// Every modifier the player picked, missing ones count as never picked.
+#[derive(QueryData)]
+pub struct BoomerangModifiers {
+    distance: Option<&'static ThrowDistance>,
+    wraps: Option<&'static ExtraWraps>,
+    spin: Option<&'static Spin>,
+    damage: Option<&'static ExtraDamage>,
+    multishot: Option<&'static Multishot>,
+    hits: Option<&'static BoomerangHits>,
+}
+
+impl BoomerangModifiersItem<'_> {
+    pub fn max_distance(&self) -> f32 {
+        BASE_THROW_DISTANCE + self.distance.map_or(0.0, |distance| **distance)
+    }
+
+    pub fn max_wraps(&self) -> usize {
+        BASE_MAX_WRAPS + self.wraps.map_or(0, |wraps| **wraps)
+    }
+
+    pub fn spin(&self) -> f32 {
+        1.0 + self.spin.map_or(0.0, |spin| **spin)
+    }
+
+    pub fn damage(&self) -> i32 {
+        1 + self.damage.map_or(0, |damage| **damage)
+    }
+
+    pub fn hits(&self) -> BoomerangHits {
+        self.hits.copied().unwrap_or_default()
+    }
+
+    /// Angles of the boomerangs of a throw, relative to the aim.
+    pub fn throw_angles(&self) -> impl Iterator<Item = f32> {
+        let count = 1 + self.multishot.map_or(0, |count| **count);
+        let first = -MULTISHOT_ANGLE * (count - 1) as f32 / 2.0;
+        (0..count).map(move |index| first + MULTISHOT_ANGLE * index as f32)
+    }
+}
@@ -0,0 +1,521 @@
+use std::time::{Duration, Instant};
+
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_trauma_shake::Shake;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::characters::bullet::patterns::prelude::*;
+use crate::characters::bullet::{BulletMaxWrap, BulletWrapCount, bullet_base};
+use crate::characters::enemies::behaviour::prelude::*;
+use crate::characters::enemies::boss::Boss;
+use crate::characters::enemies::coffin::CoffinAssets;
+use crate::characters::enemies::ghost::GhostAssets;
+use crate::characters::enemies::hand::{Hand, HandAssets, WindUp};
+use crate::characters::enemies::prelude::*;
+use crate::characters::enemies::{
+    Boid, EnemyAssets, PlayerHitEvent, boids_calculate_separation, rebuild_spatial_hash,
+};
+use crate::characters::player::shoot::PlayerBoomerang;
+use crate::characters::player::{Player, PlayerAssets, PlayerHitbox, PlayerHurtbox};
+use crate::characters::prelude::*;
+use crate::characters::{HALF_HEIGHT, HALF_WIDTH, ScreenWrap, ScreenWrapEvent};
+use crate::screens::prelude::*;
+
+/// `characters_plugin` on the headless plugins, with unloaded assets and past the loading screen.
+fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(headless_plugins)
+        .add_plugins(characters_plugin)
+        .insert_state(GameScreen::SplashNext)
+        .insert_resource(PlayerAssets::mock())
+        .insert_resource(EnemyAssets::mock())
+        .insert_resource(GhostAssets { sprite: default() })
+        .insert_resource(CoffinAssets {
+            sprite: default(),
+            spawn_sound: default(),
+        })
+        .insert_resource(HandAssets {
+            sprite: default(),
+            finger: default(),
+        });
+    app.world_mut().spawn(Shake::default());
+    app.finish();
+    app.cleanup();
+    app.update();
+    app
+}
+
+fn cursor<E: Event>(app: &App) -> EventCursor<E> {
+    app.world().resource::<Events<E>>().get_cursor()
+}
+
+fn read<E: Event, T>(app: &App, cursor: &mut EventCursor<E>, f: impl Fn(&E) -> T) -> Vec<T> {
+    cursor
+        .read(app.world().resource::<Events<E>>())
+        .map(f)
+        .collect()
+}
+
+/// Updates `app` until `done` holds, for at most `max_updates` fixed ticks.
+fn update_until(app: &mut App, max_updates: usize, mut done: impl FnMut(&mut App) -> bool) -> bool {
+    for _ in 0..max_updates {
+        app.update();
+        if done(app) {
+            return true;
+        }
+    }
+    false
+}
+
+fn place(app: &mut App, entity: Entity, position: Vec2) {
+    app.world_mut()
+        .get_mut::<Transform>(entity)
+        .unwrap()
+        .translation = position.extend(0.0);
+}
+
+fn boomerang(app: &mut App, position: Vec2, velocity: Vec2) -> Entity {
+    app.world_mut()
+        .spawn(bullet_base(10.0))
+        .insert((
+            BulletMaxWrap(1),
+            PlayerBoomerang::new(64.0),
+            Collider::ball(4.0),
+            Transform::from_translation(position.extend(0.0)),
+            Velocity::linear(velocity),
+        ))
+        .id()
+}
+
+#[test]
+fn screen_wrap_mirrors_and_reports() {
+    let mut app = test_app();
+    let mut wraps = cursor::<ScreenWrapEvent>(&app);
+    let entity = app
+        .world_mut()
+        .spawn((
+            ScreenWrap,
+            Transform::from_xyz(HALF_WIDTH as f32 + 10.0, 20.0, 0.0),
+        ))
+        .id();
+    app.update();
+
+    let translation = app.world().get::<Transform>(entity).unwrap().translation;
+    assert_eq!(translation.x, -(HALF_WIDTH as f32) - 8.0);
+    assert_eq!(translation.y, -20.0);
+    assert_eq!(read(&app, &mut wraps, |event| event.entity), vec![entity]);
+
+    // back inside the screen, nothing happens
+    app.update();
+    assert!(read(&app, &mut wraps, |event| event.entity).is_empty());
+}
+
+#[test]
+fn bullets_despawn_after_max_wraps() {
+    let mut app = test_app();
+    let bullet = boomerang(&mut app, Vec2::ZERO, Vec2::ZERO);
+    let outside = Vec2::new(HALF_WIDTH as f32 + 10.0, 0.0);
+
+    place(&mut app, bullet, outside);
+    let wrapped = update_until(&mut app, 4, |app| {
+        app.world()
+            .get::<BulletWrapCount>(bullet)
+            .is_some_and(|count| **count == 1)
+    });
+    assert!(wrapped);
+    app.update();
+    assert!(
+        app.world().get_entity(bullet).is_ok(),
+        "one wrap is allowed"
+    );
+
+    place(&mut app, bullet, outside);
+    let despawned = update_until(&mut app, 4, |app| app.world().get_entity(bullet).is_err());
+    assert!(despawned, "the second wrap is over `BulletMaxWrap(1)`");
+}
+
+#[test]
+fn boomerangs_deal_damage_after_wrapping() {
+    let mut app = test_app();
+    let boomerang = boomerang(&mut app, Vec2::ZERO, Vec2::ZERO);
+    app.update();
+    assert!(app.world().get::<Damage>(boomerang).is_none());
+
+    place(&mut app, boomerang, Vec2::new(0.0, -200.0));
+    let damaging = update_until(&mut app, 4, |app| {
+        app.world().get::<Damage>(boomerang).is_some()
+    });
+    assert!(damaging);
+}
+
+#[test]
+fn boomerangs_deal_damage_past_max_distance() {
+    let mut app = test_app();
+    app.world_mut()
+        .spawn((Player, Transform::from_xyz(0.0, 120.0, 0.0)));
+    // 200 pixels per second cover the 64 pixels in about a third of a second
+    let boomerang = boomerang(&mut app, Vec2::ZERO, Vec2::new(200.0, 0.0));
+    app.update();
+    assert!(app.world().get::<Damage>(boomerang).is_none());
+
+    let damaging = update_until(&mut app, 30, |app| {
+        app.world().get::<Damage>(boomerang).is_some()
+    });
+    assert!(damaging);
+}
+
+#[test]
+fn enemies_die_at_zero_health() {
+    let mut app = test_app();
+    let mut deaths = cursor::<EnemyDiedEvent>(&app);
+    let enemy = app.world_mut().spawn(enemy_base()).insert(Health(1)).id();
+    let hitbox = app
+        .world_mut()
+        .spawn((
+            Transform::default(),
+            Collider::ball(4.0),
+            Sensor,
+            ActiveCollisionTypes::all(),
+            CollisionGroups::new(PLAYER_HITBOX_GROUP, ENEMY_HURTBOX_GROUP),
+            PlayerHitbox,
+            Damage(1),
+        ))
+        .id();
+
+    let mut died = Vec::new();
+    update_until(&mut app, 8, |app| {
+        died.extend(read(app, &mut deaths, |event| (event.enemy, event.killer)));
+        !died.is_empty()
+    });
+    assert_eq!(died, vec![(enemy, hitbox)]);
+    app.update();
+    assert!(app.world().get_entity(enemy).is_err());
+}
+
+#[test]
+fn bosses_despawn_after_their_death_cinematic() {
+    let mut app = test_app();
+    let boss = app
+        .world_mut()
+        .spawn(enemy_base())
+        .insert((
+            Boss::builder().finger_count(3).summon_count(1).build(),
+            Health(1),
+            MaxHealth(1),
+        ))
+        .id();
+    app.world_mut().spawn((
+        Transform::default(),
+        Collider::ball(4.0),
+        Sensor,
+        ActiveCollisionTypes::all(),
+        CollisionGroups::new(PLAYER_HITBOX_GROUP, ENEMY_HURTBOX_GROUP),
+        PlayerHitbox,
+        Damage(1),
+    ));
+
+    let relative_speed = |app: &App| app.world().resource::<Time<Virtual>>().relative_speed();
+    let slowed_down = update_until(&mut app, 8, |app| relative_speed(app) < 1.0);
+    assert!(slowed_down, "dying bosses slow the game down");
+    assert!(app.world().get::<Dead>(boss).is_some());
+
+    let despawned = update_until(&mut app, 400, |app| app.world().get_entity(boss).is_err());
+    assert!(despawned);
+    assert_eq!(
+        app.world().resource::<Time<Virtual>>().relative_speed(),
+        1.0
+    );
+}
+
+fn player_hits(iframes: bool) -> usize {
+    let mut app = test_app();
+    let mut hits = cursor::<PlayerHitEvent>(&app);
+    let mut player = app.world_mut().spawn(character_base());
+    player.insert((
+        Player,
+        PlayerHurtbox,
+        CollisionGroups::new(PLAYER_HURTBOX_GROUP, ENEMY_HITBOX_GROUP),
+        ActiveEvents::COLLISION_EVENTS,
+    ));
+    if iframes {
+        player.insert(Iframes);
+    }
+    // off center so the knockback has a direction
+    app.world_mut().spawn((
+        EnemyHitbox,
+        Transform::from_xyz(4.0, 0.0, 0.0),
+        Sensor,
+        Collider::ball(8.0),
+        ActiveCollisionTypes::all(),
+        Damage(1),
+        CollisionGroups::new(ENEMY_HITBOX_GROUP, PLAYER_HURTBOX_GROUP),
+        ActiveEvents::COLLISION_EVENTS,
+    ));
+
+    let mut count = 0;
+    for _ in 0..8 {
+        app.update();
+        count += read(&app, &mut hits, |_| ()).len();
+    }
+    count
+}
+
+#[test]
+fn iframes_suppress_player_hits() {
+    assert_eq!(player_hits(false), 1);
+    assert_eq!(player_hits(true), 0);
+}
+
+#[test]
+fn hit_once_damages_once_per_pass() {
+    let mut app = test_app();
+    let enemy = app.world_mut().spawn(enemy_base()).insert(Health(10)).id();
+    let hitbox = app
+        .world_mut()
+        .spawn((
+            Transform::default(),
+            Collider::ball(4.0),
+            Sensor,
+            ActiveCollisionTypes::all(),
+            CollisionGroups::new(PLAYER_HITBOX_GROUP, ENEMY_HURTBOX_GROUP),
+            PlayerHitbox,
+            HitOnce::default(),
+            Damage(1),
+        ))
+        .id();
+    let health = |app: &App| **app.world().get::<Health>(enemy).unwrap();
+
+    for _ in 0..8 {
+        app.update();
+    }
+    assert_eq!(health(&app), 9, "the overlap lasts a single pass");
+
+    // leaving and coming back is a new pass
+    place(&mut app, hitbox, Vec2::new(0.0, 64.0));
+    let left = update_until(&mut app, 8, |app| {
+        app.world().get::<HitOnce>(hitbox).unwrap().is_empty()
+    });
+    assert!(left);
+    place(&mut app, hitbox, Vec2::ZERO);
+    update_until(&mut app, 8, |app| health(app) < 9);
+    assert_eq!(health(&app), 8);
+}
+
+#[test]
+fn behaviours_follow_their_transitions() {
+    let tick = Duration::from_millis(100);
+    let mut behaviour = Behaviour::ranged(64.0)
+        .with(BehaviourState::new("telegraph", Hold).after(tick * 2, "charge"))
+        .with(
+            BehaviourState::new(
+                "charge",
+                Charge {
+                    speed_multiplier: 2.0,
+                },
+            )
+            .after(tick, APPROACH),
+        );
+    assert_eq!(behaviour.validate(), Ok(()));
+    let mut steer = |distance: f32| behaviour.steer(tick, Vec2::X, distance, 10.0);
+
+    assert_eq!(steer(100.0), Some(Vec2::new(10.0, 0.0)));
+    assert_eq!(steer(32.0), None, "in range, it holds its position");
+    assert_eq!(steer(100.0), Some(Vec2::new(10.0, 0.0)));
+
+    behaviour.switch_to("telegraph");
+    assert_eq!(behaviour.steer(tick, Vec2::X, 100.0, 10.0), None);
+    assert_eq!(behaviour.state(), "telegraph");
+    assert_eq!(
+        behaviour.steer(tick, Vec2::X, 100.0, 10.0),
+        Some(Vec2::new(20.0, 0.0))
+    );
+    behaviour.steer(tick, Vec2::X, 100.0, 10.0);
+    assert_eq!(behaviour.state(), APPROACH);
+
+    let typo = Behaviour::new(BehaviourState::new(APPROACH, Approach).after(tick, "hlod"));
+    assert!(typo.validate().is_err(), "transitions must lead to a state");
+    let twice = Behaviour::melee().with(BehaviourState::new(APPROACH, Hold));
+    assert!(twice.validate().is_err(), "state names must be unique");
+}
+
+#[test]
+fn hands_lock_their_aim_while_winding_up() {
+    let mut app = test_app();
+    let hand = app
+        .world_mut()
+        .spawn((
+            Hand::builder()
+                .shoot_rate(Duration::from_millis(300))
+                .wind_up(Duration::from_millis(150))
+                .build(),
+            Transform::default(),
+            AimDir(Vec2::X),
+            LookAtPlayer,
+        ))
+        .id();
+    let fingers = |app: &mut App| {
+        app.world_mut()
+            .query_filtered::<(), With<EnemyBullet>>()
+            .iter(app.world())
+            .count()
+    };
+
+    let winding_up = update_until(&mut app, 60, |app| {
+        app.world().get::<WindUp>(hand).is_some()
+    });
+    assert!(winding_up);
+    assert!(app.world().get::<LookAtPlayer>(hand).is_none());
+    assert_eq!(fingers(&mut app), 0, "the volley comes after the wind up");
+
+    let shot = update_until(&mut app, 60, |app| fingers(app) > 0);
+    assert!(shot);
+    assert!(app.world().get::<WindUp>(hand).is_none());
+    assert!(app.world().get::<LookAtPlayer>(hand).is_some());
+}
+
+#[test]
+fn bullet_patterns_fire_every_volley_then_stop() {
+    let mut app = test_app();
+    let pattern = BulletPattern {
+        shape: PatternShape::Spiral {
+            count: 4,
+            step: 90.0,
+            interval: 0.05,
+        },
+        ..default()
+    };
+    let shooter = app
+        .world_mut()
+        .spawn((
+            FirePattern::new(pattern, default()),
+            Transform::default(),
+            AimDir(Vec2::X),
+        ))
+        .id();
+
+    let done = update_until(&mut app, 60, |app| {
+        app.world().get::<FirePattern>(shooter).is_none()
+    });
+    assert!(done);
+    let directions = app
+        .world_mut()
+        .query_filtered::<&AimDir, With<EnemyBullet>>()
+        .iter(app.world())
+        .map(|aim_dir| aim_dir.round())
+        .collect::<Vec<_>>();
+    assert_eq!(directions.len(), 4, "one bullet per volley");
+    for dir in [Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y] {
+        assert!(
+            directions.contains(&dir),
+            "every volley turns a step further"
+        );
+    }
+}
+
+/// The separation of a boid worked out by [`boids_calculate_separation_full_scan`].
+#[derive(Component, Debug, Default)]
+struct FullScanSeparation(Vec2);
+
+/// The separation before `SpatialHash`, every boid checked against every other one.
+fn boids_calculate_separation_full_scan(
+    mut query: Query<(&Transform, &Boid, &mut FullScanSeparation), With<Enemy>>,
+) {
+    let positions = query
+        .iter()
+        .map(|(transform, ..)| transform.translation.xy())
+        .collect::<Vec<_>>();
+    query
+        .par_iter_mut()
+        .for_each(|(transform, boid, mut full_scan)| {
+            let position = transform.translation.xy();
+            let range_squared = boid.flock_detection_range() * boid.flock_detection_range();
+            let (count, separation) = positions
+                .iter()
+                .filter(|other| other.distance_squared(position) < range_squared)
+                .map(|other| (position - *other).normalize())
+                .filter(|vec| !vec.is_nan())
+                .fold((0.0f32, Vec2::ZERO), |(count, acc), vec| {
+                    (count + 1.0, acc + vec)
+                });
+            full_scan.0 = if count == 0.0 {
+                Vec2::ZERO
+            } else {
+                separation / count
+            };
+        });
+}
+
+fn spawn_random_boids(app: &mut App, rng: &mut impl Rng, count: usize) {
+    let (width, height) = (HALF_WIDTH as f32, HALF_HEIGHT as f32);
+    for _ in 0..count {
+        let position = Vec2::new(
+            rng.random_range(-width..width),
+            rng.random_range(-height..height),
+        );
+        app.world_mut().spawn((
+            Enemy,
+            Transform::from_translation(position.extend(0.0)),
+            FullScanSeparation::default(),
+        ));
+    }
+}
+
+fn separation_schedules() -> (Schedule, Schedule) {
+    let mut full_scan = Schedule::default();
+    full_scan.add_systems(boids_calculate_separation_full_scan);
+    let mut spatial_hash = Schedule::default();
+    spatial_hash.add_systems((rebuild_spatial_hash, boids_calculate_separation).chain());
+    (full_scan, spatial_hash)
+}
+
+#[test]
+fn spatial_hash_separation_matches_a_full_scan() {
+    let mut app = test_app();
+    spawn_random_boids(&mut app, &mut StdRng::seed_from_u64(0), 300);
+    let (mut full_scan, mut spatial_hash) = separation_schedules();
+
+    full_scan.run(app.world_mut());
+    spatial_hash.run(app.world_mut());
+
+    let mut boids = app.world_mut().query::<(&Boid, &FullScanSeparation)>();
+    for (boid, FullScanSeparation(expected)) in boids.iter(app.world()) {
+        let (separation, expected) = (boid.current_separation(), *expected);
+        assert!(
+            separation.abs_diff_eq(expected, 1e-4),
+            "{separation} != {expected}"
+        );
+    }
+}
+
+/// Timing dependent, so only run on demand:
+/// `cargo test --release boid_separation_benchmark -- --ignored`
+#[test]
+#[ignore = "benchmark"]
+fn boid_separation_benchmark() {
+    const RUNS: u32 = 20;
+    let mut app = test_app();
+    spawn_random_boids(&mut app, &mut StdRng::seed_from_u64(0), 600);
+    let (mut full_scan, mut spatial_hash) = separation_schedules();
+    // the first run initializes the systems
+    full_scan.run(app.world_mut());
+    spatial_hash.run(app.world_mut());
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        full_scan.run(app.world_mut());
+    }
+    let full_scan_time = start.elapsed() / RUNS;
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        spatial_hash.run(app.world_mut());
+    }
+    let spatial_hash_time = start.elapsed() / RUNS;
+
+    assert!(
+        spatial_hash_time < full_scan_time,
+        "spatial hash {spatial_hash_time:?} isn't faster than a full scan {full_scan_time:?}"
+    );
+}
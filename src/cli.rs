@@ -0,0 +1,72 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::Cli;
+}
+
+/// Command line flags, parsed by hand since there are only a few of them.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Cli {
+    /// `--replay <path>` plays back a recorded run instead of reading the player's input.
+    pub replay: Option<PathBuf>,
+    /// `--record <path>` saves a replay of every run to `path` once it ends.
+    pub record: Option<PathBuf>,
+    /// `--simulate <seconds>` plays a run headless with a bot and prints its stats.
+    pub simulate: Option<Duration>,
+    /// `--bot <scripted|random>` picks the bot of `--simulate`.
+    pub bot: SimBot,
+    /// `--seed <n>` fixes the seed of `--simulate`.
+    pub seed: Option<u64>,
+}
+
+impl Cli {
+    /// Runs before logging is set up, so problems are printed to stderr directly.
+    pub fn parse() -> Self {
+        Self::from_args(std::env::args().skip(1))
+    }
+
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--replay" => cli.replay = args.next().map(PathBuf::from),
+                "--record" => cli.record = args.next().map(PathBuf::from),
+                "--simulate" => {
+                    cli.simulate = parse_value(&arg, args.next()).map(Duration::from_secs)
+                }
+                "--bot" => cli.bot = parse_value(&arg, args.next()).unwrap_or_default(),
+                "--seed" => cli.seed = parse_value(&arg, args.next()),
+                other => eprintln!("unknown argument `{other}`"),
+            }
+        }
+        cli
+    }
+
+    /// The headless run asked for with `--simulate`, if any.
+    pub fn simulation(&self) -> Option<SimConfig> {
+        Some(SimConfig {
+            duration: self.simulate?,
+            bot: self.bot,
+            seed: self.seed,
+        })
+    }
+}
+
+fn parse_value<T: FromStr<Err: fmt::Display>>(flag: &str, value: Option<String>) -> Option<T> {
+    let Some(value) = value else {
+        eprintln!("missing value for {flag}");
+        return None;
+    };
+    value
+        .parse()
+        .inspect_err(|error| eprintln!("invalid value `{value}` for {flag}: {error}"))
+        .ok()
+}
@@ -1,5 +1,8 @@
 #![feature(trait_alias)]
 use crate::audio::prelude::*;
+use crate::bindings::prelude::*;
+use crate::cli::prelude::*;
+use bevy::log::{Level, LogPlugin};
 use bevy::prelude::*;
 use bevy_enoki::prelude::*;
 use bevy_lunex::prelude::*;
@@ -8,16 +11,25 @@ use bevy_trauma_shake::prelude::*;
 use bevy_tweening::TweeningPlugin;
 use characters::prelude::*;
 use effects::prelude::*;
+use rng::prelude::*;
 use screens::prelude::*;
 
 mod audio;
 mod autotimer;
+mod bindings;
 mod characters;
+mod cli;
 mod effects;
 mod exp_decay;
+mod rng;
 mod screens;
 
-pub fn main() {
+pub fn main() -> AppExit {
+    let cli = Cli::parse();
+    if let Some(config) = cli.simulation() {
+        return simulate(config);
+    }
+
     let mut app = App::new();
     app.insert_resource(ClearColor(Color::BLACK))
         .add_plugins(
@@ -34,17 +46,40 @@ pub fn main() {
                     ..default()
                 }),
         )
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1.0))
+        .insert_resource(cli)
+        // stepped with `Time<Fixed>` so that replays play out exactly like the recorded run
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1.0).in_fixed_schedule())
         // .add_plugins(RapierDebugRenderPlugin::default())
         .add_plugins(EnokiPlugin)
         .add_plugins(TweeningPlugin)
         .add_plugins(TraumaPlugin)
         .add_plugins(UiLunexPlugins)
         .add_plugins(my_audio_plugin)
+        .add_plugins(bindings_plugin)
+        .add_plugins(rng_plugin)
         .add_plugins(effects_plugin)
         .add_plugins(screens_plugin)
         .add_plugins(characters_plugin);
-    app.run();
+    app.run()
+}
+
+/// Plays a headless run for `--simulate` and prints its stats.
+fn simulate(config: SimConfig) -> AppExit {
+    let mut app = headless_app(config);
+    app.add_plugins(LogPlugin {
+        level: Level::WARN,
+        ..default()
+    });
+    match run_simulation(&mut app) {
+        Ok(stats) => {
+            println!("{stats}");
+            AppExit::Success
+        }
+        Err(error) => {
+            tracing::error!(%error, "simulation failed");
+            AppExit::error()
+        }
+    }
 }
 
 pub const COLORS: &[Color] = &[
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+pub mod prelude {
+    pub use super::rng_plugin;
+    pub use super::{FixedSeed, GameRng};
+}
+
+pub fn rng_plugin(app: &mut App) {
+    app.init_resource::<GameRng>().init_resource::<FixedSeed>();
+}
+
+/// Forces the seed of every run when set, e.g. to play back a replay.
+#[derive(Resource, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct FixedSeed(pub Option<u64>);
+
+/// Seeded randomness for a run, split in streams so that e.g. shooting more
+/// does not change where enemies spawn.
+///
+/// Reseeded at the start of every run, see [`GameRng::new`].
+#[derive(Resource, Debug, Clone)]
+pub struct GameRng {
+    seed: u64,
+    spawning: StdRng,
+    weapons: StdRng,
+    effects: StdRng,
+    drops: StdRng,
+    upgrades: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        // every stream gets its own seed derived from the run seed
+        let mut streams = StdRng::seed_from_u64(seed);
+        Self {
+            seed,
+            spawning: StdRng::from_rng(&mut streams),
+            weapons: StdRng::from_rng(&mut streams),
+            effects: StdRng::from_rng(&mut streams),
+            drops: StdRng::from_rng(&mut streams),
+            upgrades: StdRng::from_rng(&mut streams),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Enemy placement and wave generation.
+    pub fn spawning(&mut self) -> &mut StdRng {
+        &mut self.spawning
+    }
+
+    /// Bullet spread.
+    pub fn weapons(&mut self) -> &mut StdRng {
+        &mut self.weapons
+    }
+
+    /// Cosmetic variation, like sound pitch.
+    pub fn effects(&mut self) -> &mut StdRng {
+        &mut self.effects
+    }
+
+    /// Pickups dropped by enemies.
+    pub fn drops(&mut self) -> &mut StdRng {
+        &mut self.drops
+    }
+
+    /// Choices offered by upgrade drafts.
+    pub fn upgrades(&mut self) -> &mut StdRng {
+        &mut self.upgrades
+    }
+}
@@ -1,11 +1,16 @@
 use crate::COLORS;
 use crate::autotimer::prelude::*;
+use crate::bindings::prelude::*;
 use crate::characters::enemies::prelude::*;
 use crate::characters::player::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::gameplay::score::prelude::*;
+use crate::screens::high_scores::prelude::*;
 use crate::screens::prelude::*;
 use crate::screens::splash::play_menu_sound;
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
+use strum::IntoEnumIterator;
 use tracing::instrument;
 
 pub mod prelude {
@@ -36,7 +41,7 @@ struct BlackScreen;
 
 const MARGIN: Val = Val::Px(4.0);
 
-fn spawn_black_screen(mut commands: Commands) {
+fn spawn_black_screen(mut commands: Commands, bindings: Single<&InputBindings>) {
     commands.spawn((
         Node {
             // fill the entire window
@@ -52,7 +57,7 @@ fn spawn_black_screen(mut commands: Commands) {
         BackgroundColor(Color::BLACK),
         BlackScreen,
         Transform::from_xyz(0.0, 0.0, 100.0),
-        DeathScreenAction::default_input_map(),
+        DeathScreenAction::input_map(&bindings),
     ));
 }
 
@@ -61,6 +66,11 @@ fn spawn_black_screen_ui(
     mut commands: Commands,
     time: Res<Time>,
     black_screen: Single<(Entity, &mut TextDelayTimer), With<BlackScreen>>,
+    score: Res<Score>,
+    high_scores: Single<&HighScores>,
+    latest: Res<LatestHighScore>,
+    bindings: Single<&InputBindings>,
+    rng: Res<GameRng>,
 ) {
     let (black_screen, mut text_delay) = black_screen.into_inner();
     text_delay.tick(time.delta());
@@ -68,10 +78,65 @@ fn spawn_black_screen_ui(
         return;
     }
     tracing::info!("spawning ui");
+    let kills = EnemyKind::iter()
+        .map(|kind| format!("{} {}", kind.plural(), score.kills(kind)))
+        .collect::<Vec<_>>()
+        .join("  ");
     commands.entity(black_screen).insert(children![
         (Text::new("DIED"), TextColor(COLORS[3])),
-        (Text::new("Press ENTER to restart."), TextColor(COLORS[4]))
+        (
+            Text::new(format!("SCORE {}", score.total)),
+            TextColor(COLORS[2])
+        ),
+        (Text::new(kills), TextColor(COLORS[3])),
+        (
+            Text::new(format!(
+                "kills {}  chain +{}  wrap +{}",
+                score.kill_points, score.chain_points, score.wrap_points
+            )),
+            TextColor(COLORS[3])
+        ),
+        (
+            Text::new(format!("best chain {}", score.best_chain)),
+            TextColor(COLORS[3])
+        ),
+        (
+            Text::new(format!("seed {}", rng.seed())),
+            TextColor(COLORS[4])
+        ),
+        (
+            Node {
+                margin: UiRect::top(MARGIN),
+                ..default()
+            },
+            Text::new("HIGH SCORES"),
+            TextColor(COLORS[2])
+        ),
     ]);
+    commands.entity(black_screen).with_children(|parent| {
+        for (position, entry) in high_scores.iter().enumerate() {
+            let color = if **latest == Some(position) {
+                COLORS[1]
+            } else {
+                COLORS[3]
+            };
+            parent.spawn((
+                Text::new(format!("{}. {}", position + 1, entry.summary())),
+                TextColor(color),
+            ));
+        }
+        parent.spawn((
+            Node {
+                margin: UiRect::top(MARGIN),
+                ..default()
+            },
+            Text::new(format!(
+                "Press {} to restart.",
+                bindings.describe(BindableAction::Confirm).to_uppercase()
+            )),
+            TextColor(COLORS[4]),
+        ));
+    });
 }
 
 #[derive(Actionlike, Debug, Reflect, PartialEq, Eq, Clone, Copy, Hash)]
@@ -80,12 +145,8 @@ enum DeathScreenAction {
 }
 
 impl DeathScreenAction {
-    fn default_input_map() -> InputMap<Self> {
-        InputMap::default()
-            .with(DeathScreenAction::Continue, KeyCode::Enter)
-            .with(DeathScreenAction::Continue, GamepadButton::C)
-            .with(DeathScreenAction::Continue, KeyCode::Space)
-            .with(DeathScreenAction::Continue, GamepadButton::RightTrigger)
+    fn input_map(bindings: &InputBindings) -> InputMap<Self> {
+        bindings.input_map(BindableAction::Confirm, DeathScreenAction::Continue)
     }
 }
 
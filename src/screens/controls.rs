@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use moonshine_save::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::COLORS;
+use crate::bindings::prelude::*;
+use crate::screens::menu::prelude::*;
+use crate::screens::{GameScreen, SAVE_PATH};
+
+pub mod prelude {
+    pub use super::controls_plugin;
+}
+
+pub fn controls_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameScreen::Controls), spawn_controls_menu)
+        .add_systems(
+            OnExit(GameScreen::Controls),
+            (despawn_controls_menu, save_input_bindings),
+        )
+        .add_systems(
+            Update,
+            (
+                capture_binding,
+                controls_menu_actions,
+                update_binding_labels,
+            )
+                .chain()
+                .run_if(in_state(GameScreen::Controls)),
+        );
+}
+
+#[derive(Component)]
+struct ControlsMenu;
+
+#[derive(Component)]
+struct ControlsHint;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlsButton {
+    Rebind(BindableAction, BindingSlot),
+    Reset,
+    Back,
+}
+
+/// Rebinding progress of the [`ControlsMenu`].
+#[derive(Component, Debug, Default)]
+struct Rebinding {
+    /// Slot waiting for the next press, the menu is locked meanwhile.
+    capturing: Option<(BindableAction, BindingSlot)>,
+    /// Action that gave up its button to the last rebind.
+    swapped: Option<BindableAction>,
+}
+
+fn spawn_controls_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            ControlsMenu,
+            Menu::default(),
+            Rebinding::default(),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(COLORS[5]),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("CONTROLS"), TextColor(COLORS[2])));
+            let mut index = 0;
+            for action in BindableAction::iter() {
+                for slot in BindingSlot::iter() {
+                    // labels are filled in by `update_binding_labels`
+                    parent.spawn((menu_button(index, ""), ControlsButton::Rebind(action, slot)));
+                    index += 1;
+                }
+            }
+            parent.spawn((menu_button(index, "reset"), ControlsButton::Reset));
+            parent.spawn((menu_button(index + 1, "back"), ControlsButton::Back));
+            parent.spawn((ControlsHint, Text::default(), TextColor(COLORS[4])));
+        });
+}
+
+/// Binds the next key, mouse button or gamepad button pressed to the slot being captured.
+/// A press on the other device cancels, so every binding stays reachable.
+fn capture_binding(
+    menu: Single<(&mut Menu, &mut Rebinding), With<ControlsMenu>>,
+    mut bindings: Single<&mut InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let (mut menu, mut rebinding) = menu.into_inner();
+    let Some((action, slot)) = rebinding.capturing else {
+        return;
+    };
+    let keyboard_press = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        });
+    let gamepad_press = gamepads
+        .iter()
+        .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+        .map(Binding::Gamepad);
+    let (pressed, cancelled) = match slot {
+        BindingSlot::KeyboardMouse => (keyboard_press, gamepad_press.is_some()),
+        BindingSlot::Gamepad => (gamepad_press, keyboard_press.is_some()),
+    };
+    if let Some(binding) = pressed.filter(|binding| slot.accepts(*binding)) {
+        rebinding.swapped = bindings.rebind(action, slot, binding);
+        tracing::info!(?action, ?slot, ?binding, swapped = ?rebinding.swapped, "rebound");
+    } else if !cancelled {
+        return;
+    }
+    rebinding.capturing = None;
+    menu.set_locked(false);
+}
+
+fn controls_menu_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut back: EventReader<MenuBack>,
+    buttons: Query<&ControlsButton>,
+    menu: Single<(&mut Menu, &mut Rebinding), With<ControlsMenu>>,
+    mut bindings: Single<&mut InputBindings>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+) {
+    let (mut menu, mut rebinding) = menu.into_inner();
+    if back.read().count() > 0 {
+        next_screen.set(GameScreen::Options);
+    }
+    for event in activated.read() {
+        match buttons.get(event.button) {
+            Ok(ControlsButton::Rebind(action, slot)) => {
+                rebinding.capturing = Some((*action, *slot));
+                rebinding.swapped = None;
+                menu.set_locked(true);
+            }
+            Ok(ControlsButton::Reset) => {
+                **bindings = InputBindings::default();
+                rebinding.swapped = None;
+            }
+            Ok(ControlsButton::Back) => next_screen.set(GameScreen::Options),
+            Err(_) => {}
+        }
+    }
+}
+
+fn update_binding_labels(
+    rebinding: Single<&Rebinding, With<ControlsMenu>>,
+    bindings: Single<&InputBindings>,
+    mut buttons: Query<(&ControlsButton, &mut Text)>,
+    mut hint: Single<&mut Text, (With<ControlsHint>, Without<ControlsButton>)>,
+) {
+    for (button, mut text) in buttons.iter_mut() {
+        let ControlsButton::Rebind(action, slot) = *button else {
+            continue;
+        };
+        let label = if rebinding.capturing == Some((action, slot)) {
+            format!("{action} {slot}: ...")
+        } else {
+            format!("{action} {slot}: {}", bindings.get(action).get(slot))
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+
+    let hint_text = match (rebinding.capturing, rebinding.swapped) {
+        (Some((_, BindingSlot::KeyboardMouse)), _) => {
+            "press a key or mouse button, any gamepad button cancels".to_string()
+        }
+        (Some((_, BindingSlot::Gamepad)), _) => {
+            "press a gamepad button, any key cancels".to_string()
+        }
+        (None, Some(swapped)) => format!("swapped with {swapped}"),
+        (None, None) => String::new(),
+    };
+    if hint.0 != hint_text {
+        hint.0 = hint_text;
+    }
+}
+
+fn despawn_controls_menu(mut commands: Commands, menu: Single<Entity, With<ControlsMenu>>) {
+    commands.entity(*menu).try_despawn();
+}
+
+fn save_input_bindings(mut commands: Commands) {
+    commands.trigger_save(SaveWorld::default_into_file(SAVE_PATH));
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bon::Builder;
+use rand::seq::SliceRandom;
+use strum::IntoEnumIterator;
+
+use crate::characters::player::Player;
+use crate::characters::player::upgrades::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::gameplay::replay::prelude::*;
+use crate::screens::gameplay::{WaveCleared, track_wave_deaths};
+use crate::screens::pause::prelude::*;
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::draft_plugin;
+    pub use super::{Draft, DraftConfig, UpgradePicked};
+}
+
+pub fn draft_plugin(app: &mut App) {
+    app.init_resource::<DraftConfig>()
+        .init_resource::<Draft>()
+        .add_event::<UpgradePicked>()
+        .add_systems(OnEnter(GameScreen::Gameplay), reset_draft)
+        .add_systems(OnEnter(GameplayState::Drafting), freeze_game)
+        .add_systems(OnExit(GameplayState::Drafting), unfreeze_game)
+        .add_systems(
+            FixedUpdate,
+            start_draft
+                .after(track_wave_deaths)
+                .run_if(in_state(GameplayState::Running)),
+        )
+        .add_systems(
+            Update,
+            apply_picked_upgrade.run_if(in_state(GameplayState::Drafting)),
+        );
+}
+
+/// How often upgrade drafts are offered.
+#[derive(Resource, Debug, Clone, Builder)]
+pub struct DraftConfig {
+    /// A draft is offered once every `every_waves` cleared waves, `0` turns drafts off.
+    pub every_waves: usize,
+    /// Upgrades to pick from in a draft.
+    pub choices: usize,
+}
+
+impl Default for DraftConfig {
+    fn default() -> Self {
+        DraftConfig::builder().every_waves(3).choices(3).build()
+    }
+}
+
+/// The upgrades offered by the current draft.
+#[derive(Resource, Debug, Default)]
+pub struct Draft {
+    pub choices: Vec<Upgrade>,
+    /// Waves cleared during the run, whichever ones they were.
+    cleared_waves: usize,
+}
+
+/// Ends the draft with `upgrade` stacked onto the player.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UpgradePicked {
+    pub upgrade: Upgrade,
+}
+
+fn reset_draft(mut draft: ResMut<Draft>) {
+    *draft = Draft::default();
+}
+
+fn start_draft(
+    mut events: EventReader<WaveCleared>,
+    config: Res<DraftConfig>,
+    session: Res<ReplaySession>,
+    player: Single<BoomerangModifiers, With<Player>>,
+    mut draft: ResMut<Draft>,
+    mut rng: ResMut<GameRng>,
+    mut next: ResMut<NextState<GameplayState>>,
+) {
+    // the picks of a replay are applied on the tick they were made instead
+    if config.every_waves == 0 || session.mode == ReplayMode::Playing {
+        events.clear();
+        return;
+    }
+    let mut offered = None;
+    for &WaveCleared { wave } in events.read() {
+        draft.cleared_waves += 1;
+        if draft.cleared_waves % config.every_waves == 0 {
+            offered = Some(wave);
+        }
+    }
+    let Some(wave) = offered else {
+        return;
+    };
+    let mut choices = Upgrade::iter()
+        .filter(|upgrade| upgrade.available(&player))
+        .collect::<Vec<_>>();
+    choices.shuffle(rng.upgrades());
+    choices.truncate(config.choices);
+    tracing::info!(wave, ?choices, "upgrade draft");
+    draft.choices = choices;
+    next.set(GameplayState::Drafting);
+}
+
+fn apply_picked_upgrade(
+    mut commands: Commands,
+    mut events: EventReader<UpgradePicked>,
+    player: Single<Entity, With<Player>>,
+    mut session: ResMut<ReplaySession>,
+    mut next: ResMut<NextState<GameplayState>>,
+) {
+    let Some(&UpgradePicked { upgrade }) = events.read().last() else {
+        return;
+    };
+    tracing::info!(?upgrade, "picked upgrade");
+    upgrade.apply(&mut commands.entity(*player));
+    session.record_upgrade(upgrade);
+    next.set(GameplayState::Running);
+}
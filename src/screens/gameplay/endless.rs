@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use strum::IntoEnumIterator;
+
+use crate::characters::enemies::prelude::*;
+use crate::screens::GameScreen;
+use crate::screens::gameplay::waves::prelude::*;
+
+pub mod prelude {
+    pub use super::EndlessWaves;
+    pub use super::endless_plugin;
+}
+
+pub fn endless_plugin(app: &mut App) {
+    app.init_resource::<EndlessWaves>()
+        .add_systems(OnEnter(GameScreen::Gameplay), reset_endless_waves);
+}
+
+/// Waves generated from a point budget after the scripted [`WaveSet`] runs out.
+#[derive(Resource, Debug, Default)]
+pub struct EndlessWaves {
+    waves: Vec<Wave>,
+}
+
+impl EndlessWaves {
+    /// Returns the wave at `index`, counting every wave of `wave_set` first and
+    /// generating endless waves up to `index` from `rng` as needed.
+    pub fn wave<'a>(
+        &'a mut self,
+        wave_set: &'a WaveSet,
+        index: usize,
+        rng: &mut impl Rng,
+    ) -> Option<&'a Wave> {
+        let Some(endless_index) = index.checked_sub(wave_set.waves.len()) else {
+            return wave_set.waves.get(index);
+        };
+        let start = wave_set
+            .waves
+            .last()
+            .map_or(Duration::ZERO, |wave| wave.timestamp);
+        let params = &wave_set.endless;
+        while self.waves.len() <= endless_index {
+            let generated = self.waves.len() as u32 + 1;
+            let timestamp = start + params.interval * generated;
+            let budget = params.initial_budget
+                + params.budget_growth * (params.interval * (generated - 1)).as_secs_f32();
+            let wave = generate_wave(rng, params, timestamp, budget);
+            tracing::info!(?wave, budget, "generated endless wave");
+            self.waves.push(wave);
+        }
+        self.waves.get(endless_index)
+    }
+}
+
+fn generate_wave(
+    rng: &mut impl Rng,
+    params: &EndlessParams,
+    timestamp: Duration,
+    budget: f32,
+) -> Wave {
+    let mut wave = Wave {
+        timestamp,
+        ghost_count: 0,
+        coffin_count: 0,
+        hand_count: 0,
+        coffin: default(),
+        hand: default(),
+        boss: None,
+        advance: default(),
+    };
+    let mut budget = budget;
+    loop {
+        let affordable = EnemyKind::iter()
+            .filter_map(|kind| Some((kind, params.cost(kind)?)))
+            .filter(|(_, cost)| *cost <= budget)
+            .collect::<Vec<_>>();
+        let Some(&(kind, cost)) = affordable.choose(rng) else {
+            break;
+        };
+        if let Some(count) = wave.count_mut(kind) {
+            *count += 1;
+        }
+        budget -= cost;
+    }
+    wave
+}
+
+fn reset_endless_waves(mut endless: ResMut<EndlessWaves>) {
+    *endless = EndlessWaves::default();
+}
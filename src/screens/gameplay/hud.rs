@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use leafwing_abilities::prelude::*;
+
+use crate::COLORS;
+use crate::characters::enemies::boss::Boss;
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::Player;
+use crate::characters::player::prelude::*;
+use crate::characters::player::shoot::BoomerangCharges;
+use crate::characters::prelude::*;
+use crate::screens::gameplay::score::prelude::*;
+use crate::screens::gameplay::{CurrentWave, CurrentWaveTime};
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::HudAssets;
+    pub use super::hud_plugin;
+}
+
+pub fn hud_plugin(app: &mut App) {
+    app.configure_loading_state(
+        LoadingStateConfig::new(GameScreen::SplashFirst).load_collection::<HudAssets>(),
+    )
+    .add_systems(OnEnter(GameScreen::Gameplay), spawn_hud)
+    .add_systems(OnExit(GameScreen::Gameplay), despawn_hud)
+    .add_systems(
+        Update,
+        (update_hearts, update_hud_texts, update_boss_bar).run_if(in_state(GameScreen::Gameplay)),
+    );
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct HudAssets {
+    /// White, tinted with [`COLORS`] wherever it's drawn.
+    #[asset(path = "ui/heart.png")]
+    pub heart: Handle<Image>,
+}
+
+const FULL_HEART: Color = COLORS[1];
+const EMPTY_HEART: Color = COLORS[4];
+
+/// Root of the HUD, drawn on the low-res canvas by the [`InGameCamera`] so it stays
+/// as pixel-crisp as the game.
+#[derive(Component)]
+struct Hud;
+
+/// The `n`th heart of the player's health, from the left.
+#[derive(Component, Debug, Clone, Copy)]
+struct Heart(i32);
+
+/// A line of text of the HUD, filled in by `update_hud_texts`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum HudText {
+    Wave,
+    Score,
+    Enemies,
+    /// Cooldown of one of the player's abilities.
+    Ability(PlayerAbility),
+    Charges,
+}
+
+/// Health bar of the current boss, hidden while there's none.
+#[derive(Component)]
+struct BossBar;
+
+/// The filled part of the [`BossBar`].
+#[derive(Component)]
+struct BossBarFill;
+
+/// Characters in an ability's cooldown bar.
+const COOLDOWN_BAR_WIDTH: usize = 6;
+
+fn spawn_hud(
+    mut commands: Commands,
+    camera: Single<Entity, With<InGameCamera>>,
+    assets: Res<HudAssets>,
+    config: Res<PlayerConfig>,
+) {
+    let font = TextFont {
+        font_size: 10.0,
+        ..default()
+    };
+    let heart = ImageNode::new(assets.heart.clone()).with_color(FULL_HEART);
+    let row = Node {
+        width: Val::Percent(100.),
+        justify_content: JustifyContent::SpaceBetween,
+        ..default()
+    };
+    let boss_bar = (
+        BossBar,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(14.0),
+            left: Val::Percent(20.),
+            width: Val::Percent(60.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(1.0),
+            ..default()
+        },
+        Visibility::Hidden,
+        children![
+            (Text::new("BOSS"), TextColor(COLORS[1]), font.clone()),
+            (
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Px(4.0),
+                    ..default()
+                },
+                BackgroundColor(COLORS[4]),
+                children![(
+                    BossBarFill,
+                    Node {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(COLORS[1]),
+                )],
+            ),
+        ],
+    );
+    commands
+        .spawn((
+            Hud,
+            UiTargetCamera(*camera),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(boss_bar);
+            parent.spawn(row.clone()).with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn(Node {
+                                column_gap: Val::Px(1.0),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for index in 0..config.max_health {
+                                    parent.spawn((Heart(index), heart.clone()));
+                                }
+                            });
+                        parent.spawn((
+                            HudText::Score,
+                            Text::default(),
+                            TextColor(COLORS[2]),
+                            font.clone(),
+                        ));
+                    });
+                parent.spawn((
+                    HudText::Wave,
+                    Text::default(),
+                    TextColor(COLORS[3]),
+                    font.clone(),
+                ));
+            });
+            parent.spawn(row).with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for ability in [
+                            PlayerAbility::Dash,
+                            PlayerAbility::Recall,
+                            PlayerAbility::BloodNova,
+                        ] {
+                            parent.spawn((
+                                HudText::Ability(ability),
+                                Text::default(),
+                                TextColor(COLORS[3]),
+                                font.clone(),
+                            ));
+                        }
+                    });
+                parent.spawn((
+                    HudText::Charges,
+                    Text::default(),
+                    TextColor(COLORS[3]),
+                    font.clone(),
+                ));
+                parent.spawn((
+                    HudText::Enemies,
+                    Text::default(),
+                    TextColor(COLORS[3]),
+                    font.clone(),
+                ));
+            });
+        });
+}
+
+fn despawn_hud(mut commands: Commands, hud: Single<Entity, With<Hud>>) {
+    commands.entity(*hud).try_despawn();
+}
+
+fn update_hearts(
+    health: Single<&Health, (With<Player>, Changed<Health>)>,
+    mut hearts: Query<(&Heart, &mut ImageNode)>,
+) {
+    for (heart, mut image) in hearts.iter_mut() {
+        image.color = if heart.0 < **health {
+            FULL_HEART
+        } else {
+            EMPTY_HEART
+        };
+    }
+}
+
+fn ability_label(
+    ability: PlayerAbility,
+    cooldowns: Option<&CooldownState<PlayerAbility>>,
+) -> String {
+    let cooldown = cooldowns.and_then(|cooldowns| cooldowns.get(&ability));
+    let Some(cooldown) = cooldown else {
+        return String::new();
+    };
+    let name = ability.name();
+    if cooldown.ready().is_ok() {
+        return format!("{name} READY");
+    }
+    let charged = 1.0 - cooldown.remaining().as_secs_f32() / cooldown.max_time().as_secs_f32();
+    let filled = ((charged * COOLDOWN_BAR_WIDTH as f32) as usize).min(COOLDOWN_BAR_WIDTH);
+    format!(
+        "{name} [{}{}]",
+        "#".repeat(filled),
+        "-".repeat(COOLDOWN_BAR_WIDTH - filled)
+    )
+}
+
+fn update_hud_texts(
+    wave: Res<CurrentWave>,
+    wave_time: Res<CurrentWaveTime>,
+    score: Res<Score>,
+    enemies: Query<(), With<Enemy>>,
+    cooldowns: Query<&CooldownState<PlayerAbility>, With<Player>>,
+    charges: Query<&BoomerangCharges, With<Player>>,
+    mut texts: Query<(&HudText, &mut Text)>,
+) {
+    for (hud_text, mut text) in texts.iter_mut() {
+        let label = match hud_text {
+            HudText::Wave => {
+                let secs = wave_time.as_secs();
+                format!("wave {} {}:{:02}", **wave, secs / 60, secs % 60)
+            }
+            HudText::Score => format!("score {}", score.total),
+            HudText::Enemies => format!("enemies {}", enemies.iter().count()),
+            HudText::Ability(ability) => ability_label(*ability, cooldowns.iter().next()),
+            HudText::Charges => charges.iter().next().map_or(String::new(), |charges| {
+                format!("ammo {}/{}", charges.current, charges.max)
+            }),
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+fn update_boss_bar(
+    bosses: Query<(&Health, &MaxHealth), (With<Boss>, Without<Dead>)>,
+    mut bar: Single<&mut Visibility, With<BossBar>>,
+    mut fill: Single<&mut Node, With<BossBarFill>>,
+) {
+    let Some((health, max_health)) = bosses.iter().next() else {
+        bar.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    bar.set_if_neq(Visibility::Inherited);
+    let fraction = (**health as f32 / (**max_health).max(1) as f32).clamp(0.0, 1.0);
+    fill.width = Val::Percent(100. * fraction);
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::characters::enemies::boss::prelude::*;
+use crate::characters::enemies::coffin::prelude::*;
+use crate::characters::enemies::ghost::{CommandsGhost, prelude::*};
+use crate::characters::enemies::hand::{CommandsHand, Hand, HandArgs, HandAssets};
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::Player;
+use crate::characters::player::prelude::*;
+use crate::characters::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::gameplay::draft::prelude::*;
+use crate::screens::gameplay::endless::prelude::*;
+use crate::screens::gameplay::hud::prelude::*;
+use crate::screens::gameplay::pickups::prelude::*;
+use crate::screens::gameplay::replay::prelude::*;
+use crate::screens::gameplay::score::prelude::*;
+use crate::screens::gameplay::waves::prelude::*;
+use crate::screens::prelude::*;
+
+pub mod draft;
+pub mod endless;
+pub mod hud;
+pub mod pickups;
+pub mod replay;
+pub mod score;
+pub mod sim;
+pub mod waves;
+
+pub fn gameplay_plugin(app: &mut App) {
+    app.add_plugins(waves_plugin)
+        .add_plugins(endless_plugin)
+        .add_plugins(score_plugin)
+        .add_plugins(hud_plugin)
+        .add_plugins(pickups_plugin)
+        .add_plugins(replay_plugin)
+        .add_plugins(draft_plugin)
+        .init_resource::<CurrentWave>()
+        .init_resource::<CurrentWaveTime>()
+        .init_resource::<WaveProgress>()
+        .init_resource::<TimeAlive>()
+        .add_event::<SpawnWaveEvent>()
+        .add_event::<WaveCleared>()
+        .add_observer(count_wave_members)
+        .add_observer(inherit_wave_from_coffin)
+        .add_systems(
+            OnEnter(GameScreen::Gameplay),
+            (spawn_player, reset_wave, reseed_game_rng),
+        )
+        .add_systems(
+            OnExit(GameScreen::Gameplay),
+            (despawn_player, despawn_enemies),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                spawn_waves,
+                spawn_wave_event_loop.after(spawn_waves),
+                tick_time_alive,
+            )
+                .run_if(in_state(GameplayState::Running)),
+        )
+        .add_systems(
+            FixedUpdate,
+            track_wave_deaths
+                .before(handle_enemy_died_events)
+                .run_if(in_state(GameScreen::Gameplay)),
+        );
+}
+
+#[derive(Resource, DerefMut, Deref, Default)]
+pub struct CurrentWave(usize);
+
+#[derive(Resource, DerefMut, Deref, Default)]
+pub struct CurrentWaveTime(Duration);
+
+/// How long the player has survived in the current run.
+#[derive(Resource, DerefMut, Deref, Default)]
+pub struct TimeAlive(Duration);
+
+/// The wave an enemy was spawned by.
+#[derive(Component, Debug, Deref, Clone, Copy)]
+pub struct WaveMember(usize);
+
+/// How many enemies of each spawned wave are still alive.
+#[derive(Resource, Debug, Default)]
+pub struct WaveProgress {
+    remaining: HashMap<usize, usize>,
+}
+
+impl WaveProgress {
+    pub fn remaining(&self, wave: usize) -> usize {
+        self.remaining.get(&wave).copied().unwrap_or_default()
+    }
+}
+
+/// Fired once every enemy of a wave has died.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveCleared {
+    pub wave: usize,
+}
+
+fn random_point_on_rectangle_perimeter(
+    rng: &mut impl Rng,
+    center: Vec2,
+    width: f32,
+    height: f32,
+) -> Vec2 {
+    let perimeter = 2.0 * (width + height);
+
+    let p = rng.random_range(0.0..perimeter);
+
+    let half_width = width / 2.0;
+    let half_height = height / 2.0;
+
+    if p < width {
+        // Top edge: left to right
+        Vec2::new(center.x - half_width + p, center.y + half_height)
+    } else if p < width + height {
+        // Right edge: top to bottom
+        Vec2::new(center.x + half_width, center.y + half_height - (p - width))
+    } else if p < 2.0 * width + height {
+        // Bottom edge: right to left
+        Vec2::new(
+            center.x + half_width - (p - width - height),
+            center.y - half_height,
+        )
+    } else {
+        // Left edge: bottom to top
+        Vec2::new(
+            center.x - half_width,
+            center.y - half_height + (p - 2.0 * width - height),
+        )
+    }
+}
+
+fn rand_on_screen_outline(rng: &mut impl Rng) -> Vec2 {
+    random_point_on_rectangle_perimeter(
+        rng,
+        Vec2::ZERO,
+        RES_WIDTH as f32 + 16.,
+        RES_HEIGHT as f32 + 16.,
+    )
+}
+
+fn reseed_game_rng(
+    mut rng: ResMut<GameRng>,
+    fixed_seed: Res<FixedSeed>,
+    wave_assets: Res<WaveAssets>,
+    wave_sets: Res<Assets<WaveSet>>,
+) {
+    let seed = fixed_seed
+        .or_else(|| {
+            wave_sets
+                .get(&wave_assets.waves)
+                .and_then(|wave_set| wave_set.seed)
+        })
+        .unwrap_or_else(rand::random);
+    tracing::info!(seed, "run seed");
+    *rng = GameRng::new(seed);
+}
+
+fn spawn_waves(
+    time: Res<Time>,
+    mut current_wave: ResMut<CurrentWave>,
+    mut current_wave_time: ResMut<CurrentWaveTime>,
+    mut events: EventWriter<SpawnWaveEvent>,
+    wave_assets: Res<WaveAssets>,
+    wave_sets: Res<Assets<WaveSet>>,
+    progress: Res<WaveProgress>,
+    enemies: Query<(), (With<Enemy>, Without<Dead>)>,
+    mut endless: ResMut<EndlessWaves>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(wave_set) = wave_sets.get(&wave_assets.waves) else {
+        return;
+    };
+    **current_wave_time += time.delta();
+    let previous = current_wave.checked_sub(1);
+    let advance = previous
+        .and_then(|previous| endless.wave(wave_set, previous, rng.spawning()))
+        .map_or(WaveTrigger::Time, |previous| previous.advance.clone());
+    let Some(wave) = endless.wave(wave_set, **current_wave, rng.spawning()) else {
+        return;
+    };
+    let context = WaveTriggerContext {
+        time_reached: **current_wave_time > wave.timestamp,
+        remaining: previous.map_or(0, |previous| progress.remaining(previous)),
+        alive: enemies.iter().count(),
+    };
+    if advance.is_met(&context) {
+        // snap the clock so the following timestamps stay relative to this wave,
+        // even if it was let through early or late
+        **current_wave_time = wave.timestamp;
+        events.write(SpawnWaveEvent(**current_wave));
+        **current_wave += 1;
+    }
+}
+
+#[derive(Event, Debug, Deref, DerefMut, Clone)]
+struct SpawnWaveEvent(usize);
+
+fn spawn_wave_event_loop(
+    mut commands: Commands,
+    mut events: EventReader<SpawnWaveEvent>,
+    ghost_assets: Res<GhostAssets>,
+    coffin_assets: Res<CoffinAssets>,
+    hand_assets: Res<HandAssets>,
+    wave_assets: Res<WaveAssets>,
+    wave_sets: Res<Assets<WaveSet>>,
+    mut endless: ResMut<EndlessWaves>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(wave_set) = wave_sets.get(&wave_assets.waves) else {
+        return;
+    };
+    let rng = rng.spawning();
+    for event in events.read() {
+        if let Some(wave) = endless.wave(wave_set, **event, rng) {
+            for _ in 0..(wave.ghost_count) {
+                let pos = rand_on_screen_outline(rng);
+                commands
+                    .spawn_ghost(GhostArgs::builder().assets(&ghost_assets).build())
+                    .insert(Transform::from_translation(pos.extend(0.0)))
+                    .insert(WaveMember(**event));
+            }
+            for _ in 0..wave.coffin_count {
+                let pos = rand_on_screen_outline(rng);
+                commands
+                    .spawn_coffin(
+                        CoffinArgs::builder()
+                            .assets(&coffin_assets)
+                            .coffin(
+                                Coffin::builder()
+                                    .initial_rate(wave.coffin.initial_rate)
+                                    .rate(wave.coffin.rate)
+                                    .count(wave.coffin.count)
+                                    .spacing(wave.coffin.spacing)
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .insert(Transform::from_translation(pos.extend(0.0)))
+                    .insert(WaveMember(**event));
+            }
+            for _ in 0..wave.hand_count {
+                let pos = rand_on_screen_outline(rng);
+                commands
+                    .spawn_hand(
+                        HandArgs::builder()
+                            .assets(&hand_assets)
+                            .hand(
+                                Hand::builder()
+                                    .shoot_rate(wave.hand.shoot_rate)
+                                    .pattern(wave.hand.pattern())
+                                    .wind_up(wave.hand.wind_up)
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .insert(Transform::from_translation(pos.extend(0.0)))
+                    .insert(WaveMember(**event));
+            }
+            if let Some(params) = wave.boss {
+                let pos = rand_on_screen_outline(rng);
+                commands
+                    .spawn_boss(
+                        BossArgs::builder()
+                            .assets(&hand_assets)
+                            .health(params.health)
+                            .boss(
+                                Boss::builder()
+                                    .finger_count(params.finger_count)
+                                    .summon_count(params.summon_count)
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .insert(Transform::from_translation(pos.extend(0.0)))
+                    .insert(WaveMember(**event));
+            }
+        }
+    }
+}
+
+fn count_wave_members(
+    trigger: Trigger<OnAdd, WaveMember>,
+    members: Query<&WaveMember>,
+    mut progress: ResMut<WaveProgress>,
+) {
+    if let Ok(member) = members.get(trigger.target()) {
+        *progress.remaining.entry(**member).or_default() += 1;
+    }
+}
+
+fn inherit_wave_from_coffin(
+    trigger: Trigger<OnAdd, SpawnedByCoffin>,
+    mut commands: Commands,
+    spawned: Query<&SpawnedByCoffin>,
+    members: Query<&WaveMember>,
+) {
+    let ghost = trigger.target();
+    if let Some(member) = spawned
+        .get(ghost)
+        .ok()
+        .and_then(|SpawnedByCoffin(coffin)| members.get(*coffin).ok())
+    {
+        commands.entity(ghost).try_insert(*member);
+    }
+}
+
+fn track_wave_deaths(
+    mut events: EventReader<EnemyDiedEvent>,
+    members: Query<&WaveMember>,
+    mut progress: ResMut<WaveProgress>,
+    mut cleared: EventWriter<WaveCleared>,
+) {
+    for EnemyDiedEvent { enemy, .. } in events.read() {
+        let Ok(member) = members.get(*enemy) else {
+            continue;
+        };
+        let Some(remaining) = progress.remaining.get_mut(&**member) else {
+            continue;
+        };
+        *remaining = remaining.saturating_sub(1);
+        if *remaining == 0 {
+            tracing::info!(wave = **member, "wave cleared");
+            cleared.write(WaveCleared { wave: **member });
+        }
+    }
+}
+
+fn tick_time_alive(
+    mut time_alive: ResMut<TimeAlive>,
+    time: Res<Time>,
+    _: Single<(), (With<Player>, Without<Dead>)>,
+) {
+    **time_alive += time.delta();
+}
+
+fn reset_wave(
+    mut wave: ResMut<CurrentWave>,
+    mut wave_time: ResMut<CurrentWaveTime>,
+    mut progress: ResMut<WaveProgress>,
+    mut time_alive: ResMut<TimeAlive>,
+) {
+    **wave = 0;
+    **wave_time = Duration::ZERO;
+    progress.remaining.clear();
+    **time_alive = Duration::ZERO;
+}
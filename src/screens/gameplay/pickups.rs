@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::COLORS;
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::Player;
+use crate::characters::player::prelude::*;
+use crate::characters::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::gameplay::hud::prelude::*;
+use crate::screens::prelude::*;
+
+pub mod prelude {
+    pub use super::HeartPickup;
+    pub use super::pickups_plugin;
+}
+
+pub fn pickups_plugin(app: &mut App) {
+    app.add_systems(OnExit(GameScreen::Gameplay), despawn_pickups)
+        .add_systems(
+            FixedUpdate,
+            (drop_hearts.before(handle_enemy_died_events), collect_hearts)
+                .run_if(in_state(GameplayState::Running)),
+        );
+}
+
+/// Distance at which the player picks up a heart.
+const PICKUP_RANGE: f32 = 12.0;
+
+/// Heals the player by one when picked up, left on the ground while at full health.
+#[derive(Component, Debug)]
+pub struct HeartPickup;
+
+fn drop_hearts(
+    mut commands: Commands,
+    mut events: EventReader<EnemyDiedEvent>,
+    enemies: Query<&Transform, With<Enemy>>,
+    config: Res<PlayerConfig>,
+    assets: Res<HudAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    let chance = config.heart_drop_chance.clamp(0.0, 1.0);
+    for EnemyDiedEvent { enemy, .. } in events.read() {
+        let Ok(transform) = enemies.get(*enemy) else {
+            continue;
+        };
+        if !rng.drops().random_bool(chance) {
+            continue;
+        }
+        commands.spawn((
+            HeartPickup,
+            Sprite {
+                image: assets.heart.clone(),
+                color: COLORS[1],
+                ..default()
+            },
+            Transform::from_translation(transform.translation.with_z(1.0)),
+        ));
+    }
+}
+
+fn collect_hearts(
+    mut commands: Commands,
+    player: Single<(&Transform, &mut Health, &MaxHealth), (With<Player>, Without<Dead>)>,
+    pickups: Query<(Entity, &Transform), With<HeartPickup>>,
+) {
+    let (player_transform, mut health, max_health) = player.into_inner();
+    for (pickup, transform) in pickups.iter() {
+        if **health >= **max_health {
+            return;
+        }
+        let distance = transform
+            .translation
+            .xy()
+            .distance(player_transform.translation.xy());
+        if distance < PICKUP_RANGE {
+            **health += 1;
+            commands.entity(pickup).try_despawn();
+        }
+    }
+}
+
+fn despawn_pickups(mut commands: Commands, pickups: Query<Entity, With<HeartPickup>>) {
+    for pickup in pickups.iter() {
+        commands.entity(pickup).try_despawn();
+    }
+}
@@ -0,0 +1,421 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::characters::enemies::Enemy;
+use crate::characters::player::Player;
+use crate::characters::player::prelude::*;
+use crate::characters::player::upgrades::prelude::*;
+use crate::cli::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::GameScreen;
+
+pub mod prelude {
+    pub use super::replay_plugin;
+    pub use super::{RecordInputSystems, Replay, ReplayFrame, ReplayMode, ReplaySession};
+}
+
+/// Ticks between two transform checksums.
+const CHECKSUM_INTERVAL: u32 = 64;
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub fn replay_plugin(app: &mut App) {
+    app.init_resource::<ReplaySession>()
+        .add_systems(Startup, replay_mode_from_cli)
+        .add_systems(OnEnter(GameScreen::Gameplay), reset_replay_session)
+        .add_systems(OnExit(GameScreen::Gameplay), save_recording)
+        .add_systems(OnEnter(GameScreen::MainMenu), skip_menu_on_playback)
+        .add_systems(
+            FixedUpdate,
+            play_back_upgrades
+                .before(PlayerInputSystems::Read)
+                .run_if(replay_mode(ReplayMode::Playing))
+                .run_if(in_state(GameScreen::Gameplay)),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                record_player_input
+                    .in_set(RecordInputSystems)
+                    .run_if(replay_mode(ReplayMode::Recording)),
+                play_back_player_input.run_if(replay_mode(ReplayMode::Playing)),
+            )
+                .after(PlayerInputSystems::Read)
+                .before(PlayerInputSystems::Apply)
+                .run_if(in_state(GameScreen::Gameplay)),
+        )
+        .add_systems(
+            FixedLast,
+            checksum_transforms.run_if(in_state(GameScreen::Gameplay)),
+        );
+}
+
+/// Records the player's input, anything else that writes it, like the bot of a simulation,
+/// has to run before.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecordInputSystems;
+
+/// The player's input during a single `FixedUpdate` tick.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ReplayFrame {
+    pub movement: [f32; 2],
+    pub aim: [f32; 2],
+    pub shoot: ButtonState,
+    pub dash: ButtonState,
+    // replays recorded before the ability existed never use it
+    #[serde(default)]
+    pub recall: ButtonState,
+    #[serde(default)]
+    pub nova: ButtonState,
+}
+
+impl ReplayFrame {
+    /// Overwrites the player's input with this frame, between
+    /// [`PlayerInputSystems::Read`] and [`PlayerInputSystems::Apply`].
+    pub fn apply(
+        &self,
+        actions: &mut ActionState<PlayerAction>,
+        abilities: &mut ActionState<PlayerAbility>,
+    ) {
+        actions
+            .dual_axis_data_mut_or_default(&PlayerAction::Move)
+            .pair = Vec2::from_array(self.movement);
+        actions
+            .dual_axis_data_mut_or_default(&PlayerAction::Aim)
+            .pair = Vec2::from_array(self.aim);
+        actions
+            .button_data_mut_or_default(&PlayerAction::Shoot)
+            .state = self.shoot;
+        abilities
+            .button_data_mut_or_default(&PlayerAbility::Dash)
+            .state = self.dash;
+        abilities
+            .button_data_mut_or_default(&PlayerAbility::Recall)
+            .state = self.recall;
+        abilities
+            .button_data_mut_or_default(&PlayerAbility::BloodNova)
+            .state = self.nova;
+    }
+}
+
+/// A recorded run: its seed, the input of every tick and checksums to detect desyncs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub checksum_interval: u32,
+    /// Run length encoded as `(ticks, frame)`, the input rarely changes every tick.
+    pub frames: Vec<(u32, ReplayFrame)>,
+    /// Checksum of the player and enemy transforms after every `checksum_interval` ticks.
+    pub checksums: Vec<u64>,
+    /// Upgrades picked in drafts, with the tick they were picked after.
+    #[serde(default)]
+    pub upgrades: Vec<(u32, Upgrade)>,
+}
+
+impl Replay {
+    fn push(&mut self, frame: ReplayFrame) {
+        match self.frames.last_mut() {
+            Some((ticks, last)) if *last == frame => *ticks += 1,
+            _ => self.frames.push((1, frame)),
+        }
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.frames.iter().map(|(ticks, _)| ticks).sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BevyError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), BevyError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, ron::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    /// Runs aren't recorded.
+    #[default]
+    Off,
+    /// Every run is recorded, and saved to [`ReplaySession::record_path`] once it ends.
+    Recording,
+    /// The player's input is replaced by the loaded replay.
+    Playing,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct ReplaySession {
+    pub mode: ReplayMode,
+    pub replay: Replay,
+    /// Where recordings are saved, they're only kept in memory when missing.
+    pub record_path: Option<PathBuf>,
+    tick: u32,
+    /// Next frame to play back: an index into `replay.frames` and how many of its ticks played.
+    cursor: (usize, u32),
+    /// Next upgrade to play back, an index into `replay.upgrades`.
+    upgrade_cursor: usize,
+    desynced: bool,
+}
+
+impl ReplaySession {
+    pub fn recording(record_path: Option<PathBuf>) -> Self {
+        Self {
+            mode: ReplayMode::Recording,
+            record_path,
+            ..default()
+        }
+    }
+
+    pub fn playing(replay: Replay) -> Self {
+        Self {
+            mode: ReplayMode::Playing,
+            replay,
+            ..default()
+        }
+    }
+
+    fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let (index, played) = &mut self.cursor;
+        let (ticks, frame) = *self.replay.frames.get(*index)?;
+        *played += 1;
+        if *played >= ticks {
+            *index += 1;
+            *played = 0;
+        }
+        Some(frame)
+    }
+
+    /// The next upgrade to play back, if it was picked after the current tick.
+    fn next_upgrade(&mut self) -> Option<Upgrade> {
+        let &(tick, upgrade) = self.replay.upgrades.get(self.upgrade_cursor)?;
+        if tick > self.tick {
+            return None;
+        }
+        self.upgrade_cursor += 1;
+        Some(upgrade)
+    }
+
+    /// Whether a played back checksum differed from the recorded one.
+    pub fn desynced(&self) -> bool {
+        self.desynced
+    }
+
+    /// Records an upgrade picked from a draft, while recording.
+    pub fn record_upgrade(&mut self, upgrade: Upgrade) {
+        if self.mode == ReplayMode::Recording {
+            let tick = self.tick;
+            self.replay.upgrades.push((tick, upgrade));
+        }
+    }
+}
+
+fn replay_mode(mode: ReplayMode) -> impl Fn(Res<ReplaySession>) -> bool {
+    move |session: Res<ReplaySession>| session.mode == mode
+}
+
+fn replay_mode_from_cli(
+    cli: Res<Cli>,
+    mut session: ResMut<ReplaySession>,
+    mut fixed_seed: ResMut<FixedSeed>,
+) {
+    let Some(path) = &cli.replay else {
+        if let Some(path) = &cli.record {
+            tracing::info!(?path, "recording runs");
+            *session = ReplaySession::recording(Some(path.clone()));
+        }
+        return;
+    };
+    match Replay::load(path) {
+        Ok(replay) => {
+            tracing::info!(
+                ?path,
+                seed = replay.seed,
+                ticks = replay.ticks(),
+                "playing replay"
+            );
+            **fixed_seed = Some(replay.seed);
+            *session = ReplaySession::playing(replay);
+        }
+        Err(error) => tracing::error!(?path, %error, "failed to load replay"),
+    }
+}
+
+fn reset_replay_session(mut session: ResMut<ReplaySession>) {
+    session.tick = 0;
+    session.cursor = (0, 0);
+    session.upgrade_cursor = 0;
+    session.desynced = false;
+    if session.mode == ReplayMode::Recording {
+        session.replay = Replay {
+            checksum_interval: CHECKSUM_INTERVAL,
+            ..default()
+        };
+    }
+}
+
+fn save_recording(mut session: ResMut<ReplaySession>, rng: Res<GameRng>) {
+    if session.mode != ReplayMode::Recording {
+        return;
+    }
+    session.replay.seed = rng.seed();
+    let Some(path) = &session.record_path else {
+        return;
+    };
+    match session.replay.save(path) {
+        Ok(()) => tracing::info!(?path, ticks = session.replay.ticks(), "saved replay"),
+        Err(error) => tracing::error!(?path, %error, "failed to save replay"),
+    }
+}
+
+/// Replays start right away instead of waiting on the main menu.
+fn skip_menu_on_playback(
+    session: Res<ReplaySession>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+) {
+    if session.mode == ReplayMode::Playing {
+        next_screen.set(GameScreen::Gameplay);
+    }
+}
+
+fn button_state<A: Actionlike>(actions: &ActionState<A>, action: &A) -> ButtonState {
+    actions
+        .button_data(action)
+        .map_or(ButtonState::Released, |data| data.state)
+}
+
+fn record_player_input(
+    mut session: ResMut<ReplaySession>,
+    actions: Res<ActionState<PlayerAction>>,
+    abilities: Single<&ActionState<PlayerAbility>, With<Player>>,
+) {
+    session.replay.push(ReplayFrame {
+        movement: actions.axis_pair(&PlayerAction::Move).to_array(),
+        aim: actions.axis_pair(&PlayerAction::Aim).to_array(),
+        shoot: button_state(&*actions, &PlayerAction::Shoot),
+        dash: button_state(&**abilities, &PlayerAbility::Dash),
+        recall: button_state(&**abilities, &PlayerAbility::Recall),
+        nova: button_state(&**abilities, &PlayerAbility::BloodNova),
+    });
+}
+
+fn play_back_player_input(
+    mut session: ResMut<ReplaySession>,
+    mut actions: ResMut<ActionState<PlayerAction>>,
+    mut abilities: Single<&mut ActionState<PlayerAbility>, With<Player>>,
+) {
+    let Some(frame) = session.next_frame() else {
+        if session.tick == session.replay.ticks() {
+            tracing::info!("replay finished");
+        }
+        return;
+    };
+    frame.apply(&mut actions, &mut abilities);
+}
+
+fn play_back_upgrades(
+    mut commands: Commands,
+    mut session: ResMut<ReplaySession>,
+    player: Single<Entity, With<Player>>,
+) {
+    while let Some(upgrade) = session.next_upgrade() {
+        upgrade.apply(&mut commands.entity(*player));
+    }
+}
+
+/// Only the translation is hashed, rotations also depend on cosmetic effects like the
+/// player bobbing, which follows the time spent on earlier screens.
+///
+/// Checksums are saved with replays, so this is FNV-1a rather than the std hasher, whose
+/// algorithm can change between Rust releases.
+fn transform_hash(transform: &Transform) -> u64 {
+    transform
+        .translation
+        .to_array()
+        .into_iter()
+        .flat_map(|value| value.to_bits().to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+fn checksum_transforms(
+    mut session: ResMut<ReplaySession>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    session.tick += 1;
+    let interval = session.replay.checksum_interval.max(1);
+    if session.tick % interval != 0 {
+        return;
+    }
+    // summed so that the order enemies are stored in doesn't matter
+    let checksum = player
+        .iter()
+        .chain(enemies.iter())
+        .map(transform_hash)
+        .fold(0u64, u64::wrapping_add);
+    match session.mode {
+        ReplayMode::Off => {}
+        ReplayMode::Recording => session.replay.checksums.push(checksum),
+        ReplayMode::Playing => {
+            let index = (session.tick / interval - 1) as usize;
+            let expected = session.replay.checksums.get(index).copied();
+            if expected.is_some_and(|expected| expected != checksum) && !session.desynced {
+                session.desynced = true;
+                tracing::warn!(tick = session.tick, "replay desynced");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(shoot: ButtonState) -> ReplayFrame {
+        ReplayFrame {
+            movement: [1.0, 0.0],
+            aim: [0.0, 1.0],
+            shoot,
+            dash: ButtonState::Released,
+            recall: ButtonState::Released,
+            nova: ButtonState::Released,
+        }
+    }
+
+    #[test]
+    fn replays_play_back_the_frames_they_recorded() {
+        let frames = [
+            frame(ButtonState::Released),
+            frame(ButtonState::Released),
+            frame(ButtonState::JustPressed),
+            frame(ButtonState::Pressed),
+            frame(ButtonState::Pressed),
+            frame(ButtonState::Pressed),
+            frame(ButtonState::Released),
+        ];
+        let mut replay = Replay::default();
+        for frame in frames {
+            replay.push(frame);
+        }
+        assert_eq!(
+            replay.frames.len(),
+            4,
+            "repeated frames are run length encoded"
+        );
+        assert_eq!(replay.ticks(), 7);
+
+        let mut session = ReplaySession::playing(replay);
+        let played = std::iter::from_fn(|| session.next_frame()).collect::<Vec<_>>();
+        assert_eq!(played, frames);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::characters::bullet::BulletWrapCount;
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::shoot::PlayerBoomerang;
+use crate::screens::GameScreen;
+
+pub mod prelude {
+    pub use super::Score;
+    pub use super::score_plugin;
+}
+
+pub fn score_plugin(app: &mut App) {
+    app.init_resource::<Score>()
+        .add_systems(OnEnter(GameScreen::Gameplay), reset_score)
+        .add_systems(
+            FixedUpdate,
+            (tick_kill_chain, score_enemy_kills.after(tick_kill_chain))
+                .before(handle_enemy_died_events)
+                .run_if(in_state(GameScreen::Gameplay)),
+        );
+}
+
+/// Time after a kill in which the next one extends the chain.
+const CHAIN_WINDOW_SECS: f32 = 1.5;
+/// Kills needed to raise the multiplier by one.
+const KILLS_PER_MULTIPLIER: u32 = 5;
+const MAX_MULTIPLIER: u32 = 5;
+
+#[derive(Resource, Debug, Clone)]
+pub struct Score {
+    pub total: u64,
+    pub kills: HashMap<EnemyKind, u32>,
+    /// Points from kills before any bonus.
+    pub kill_points: u64,
+    /// Extra points from the chain multiplier.
+    pub chain_points: u64,
+    /// Extra points from boomerangs that wrapped around the screen before the kill.
+    pub wrap_points: u64,
+    pub chain: u32,
+    pub best_chain: u32,
+    chain_timer: Timer,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        let mut chain_timer = Timer::from_seconds(CHAIN_WINDOW_SECS, TimerMode::Once);
+        // no chain is running at the start of a run
+        chain_timer.tick(chain_timer.duration());
+        Self {
+            total: 0,
+            kills: HashMap::new(),
+            kill_points: 0,
+            chain_points: 0,
+            wrap_points: 0,
+            chain: 0,
+            best_chain: 0,
+            chain_timer,
+        }
+    }
+}
+
+impl Score {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.chain.saturating_sub(1) / KILLS_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+
+    pub fn kills(&self, kind: EnemyKind) -> u32 {
+        self.kills.get(&kind).copied().unwrap_or_default()
+    }
+
+    fn add_kill(&mut self, kind: EnemyKind, wraps: usize) {
+        self.chain += 1;
+        self.best_chain = self.best_chain.max(self.chain);
+        self.chain_timer.reset();
+        *self.kills.entry(kind).or_default() += 1;
+
+        let base = kill_points(kind);
+        let chain = base * u64::from(self.multiplier() - 1);
+        let wrap = base / 2 * wraps as u64;
+        self.kill_points += base;
+        self.chain_points += chain;
+        self.wrap_points += wrap;
+        self.total += base + chain + wrap;
+    }
+}
+
+fn kill_points(kind: EnemyKind) -> u64 {
+    match kind {
+        EnemyKind::Ghost => 10,
+        EnemyKind::Coffin => 50,
+        EnemyKind::Hand => 75,
+        EnemyKind::Boss => 1000,
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn tick_kill_chain(mut score: ResMut<Score>, time: Res<Time>) {
+    score.chain_timer.tick(time.delta());
+    if score.chain_timer.just_finished() {
+        tracing::debug!(chain = score.chain, "kill chain ended");
+        score.chain = 0;
+    }
+}
+
+fn score_enemy_kills(
+    mut events: EventReader<EnemyDiedEvent>,
+    mut score: ResMut<Score>,
+    enemies: Query<&EnemyKind>,
+    boomerangs: Query<&BulletWrapCount, With<PlayerBoomerang>>,
+) {
+    for EnemyDiedEvent { enemy, killer } in events.read() {
+        let Ok(kind) = enemies.get(*enemy) else {
+            continue;
+        };
+        let wraps = boomerangs.get(*killer).map_or(0, |wraps| **wraps);
+        score.add_kill(*kind, wraps);
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bevy::asset::AssetPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::render::mesh::MeshPlugin;
+use bevy::render::render_resource::Shader;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_asset_loader::prelude::*;
+use bevy_enoki::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_trauma_shake::Shake;
+use leafwing_input_manager::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use strum::IntoEnumIterator;
+use web_time::Instant;
+
+use crate::audio::prelude::*;
+use crate::bindings::prelude::*;
+use crate::characters::enemies::PlayerHitEvent;
+use crate::characters::enemies::prelude::*;
+use crate::characters::player::Player;
+use crate::characters::player::prelude::*;
+use crate::characters::prelude::*;
+use crate::cli::prelude::*;
+use crate::effects::prelude::*;
+use crate::rng::prelude::*;
+use crate::screens::gameplay::draft::prelude::*;
+use crate::screens::gameplay::replay::prelude::*;
+use crate::screens::gameplay::score::prelude::*;
+use crate::screens::gameplay::{CurrentWave, TimeAlive, gameplay_plugin, reseed_game_rng};
+use crate::screens::{GameScreen, GameplayState};
+
+pub mod prelude {
+    pub use super::{SimBot, SimConfig, SimStats, headless_app, headless_plugins, run_simulation};
+}
+
+/// Give up on a simulation whose assets take longer than this to load.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time between two decisions of [`SimBot::Random`].
+const RANDOM_DECISION_SECS: f32 = 0.5;
+/// Enemies closer than this make [`SimBot::Scripted`] flee, and dash when much closer.
+const SCRIPTED_FLEE_RANGE: f32 = 96.0;
+const SCRIPTED_DASH_RANGE: f32 = 24.0;
+
+/// A run played by a bot instead of the player, for balancing the wave table.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SimConfig {
+    /// Simulated time after which the run is stopped, if the bot is still alive.
+    pub duration: Duration,
+    pub bot: SimBot,
+    /// Seed of the run, picked the same way as a normal run when missing.
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimBot {
+    /// Kites the closest enemies while circling the arena and shooting at them.
+    #[default]
+    Scripted,
+    /// Mashes random inputs, seeded from the run.
+    Random,
+}
+
+impl fmt::Display for SimBot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SimBot::Scripted => "scripted",
+            SimBot::Random => "random",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for SimBot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scripted" => Ok(SimBot::Scripted),
+            "random" => Ok(SimBot::Random),
+            other => Err(format!(
+                "unknown bot `{other}`, expected scripted or random"
+            )),
+        }
+    }
+}
+
+/// What happened during a simulated run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimStats {
+    pub seed: u64,
+    pub bot: SimBot,
+    pub survival_time: Duration,
+    pub died: bool,
+    /// Number of waves that spawned.
+    pub waves_reached: usize,
+    pub score: u64,
+    pub kills: HashMap<EnemyKind, u32>,
+    /// Damage the player took, by the index of the latest wave at the time.
+    pub damage_per_wave: BTreeMap<usize, i32>,
+}
+
+impl fmt::Display for SimStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {} with the {} bot", self.seed, self.bot)?;
+        writeln!(
+            f,
+            "{} after {:.1}s, {} waves, score {}",
+            if self.died { "died" } else { "survived" },
+            self.survival_time.as_secs_f32(),
+            self.waves_reached,
+            self.score
+        )?;
+        write!(f, "kills:")?;
+        for kind in EnemyKind::iter() {
+            let kills = self.kills.get(&kind).copied().unwrap_or_default();
+            write!(f, " {kind:?} {kills}")?;
+        }
+        writeln!(f)?;
+        write!(f, "damage taken:")?;
+        if self.damage_per_wave.is_empty() {
+            write!(f, " none")?;
+        }
+        for (wave, damage) in &self.damage_per_wave {
+            write!(f, " wave {wave}: {damage}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An `App` that plays a run with `config.bot`, see [`headless_plugins`].
+pub fn headless_app(config: SimConfig) -> App {
+    let mut app = App::new();
+    app.add_plugins(headless_plugins)
+        .add_plugins(bindings_plugin)
+        .add_plugins(characters_plugin)
+        .add_plugins(sim_plugin)
+        .insert_resource(config)
+        .insert_resource(FixedSeed(config.seed))
+        .insert_resource(Cli::default());
+    app
+}
+
+/// Everything gameplay runs on besides the game's own plugins, without a window,
+/// a renderer or an audio device. `FixedUpdate` is stepped once per update.
+///
+/// Only the asset types of the render plugins are registered, gameplay systems spawn
+/// sprites, meshes and particles even when nothing draws them.
+pub fn headless_plugins(app: &mut App) {
+    app.add_plugins(MinimalPlugins)
+        .add_plugins((
+            StatesPlugin,
+            AssetPlugin::default(),
+            TransformPlugin,
+            InputPlugin,
+            ImagePlugin::default_nearest(),
+            MeshPlugin,
+        ))
+        // normally registered by the render plugin, particles load their shaders on build
+        .init_asset::<Shader>()
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1.0).in_fixed_schedule())
+        .add_plugins(EnokiPlugin)
+        .add_plugins(my_audio_plugin)
+        .add_plugins(rng_plugin)
+        .add_plugins(effects_plugin);
+    let timestep = app.world().resource::<Time<Fixed>>().timestep();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+}
+
+/// The screens a simulated run goes through: loading, then straight into gameplay.
+fn sim_plugin(app: &mut App) {
+    app.init_state::<GameScreen>()
+        .add_sub_state::<GameplayState>()
+        .add_loading_state(
+            LoadingState::new(GameScreen::SplashFirst).continue_to_state(GameScreen::SplashNext),
+        )
+        .add_plugins(gameplay_plugin)
+        .init_resource::<DamageTaken>()
+        .init_resource::<SimBotState>()
+        .add_systems(Startup, spawn_shake)
+        .add_systems(OnEnter(GameScreen::SplashNext), start_simulated_run)
+        .add_systems(
+            OnEnter(GameScreen::Gameplay),
+            reset_sim_bot.after(reseed_game_rng),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                // a played back replay replaces the bot
+                drive_player
+                    .after(PlayerInputSystems::Read)
+                    .before(RecordInputSystems)
+                    .before(PlayerInputSystems::Apply)
+                    .run_if(|session: Res<ReplaySession>| session.mode != ReplayMode::Playing),
+                track_damage_taken,
+            )
+                .run_if(in_state(GameplayState::Running)),
+        )
+        .add_systems(
+            Update,
+            pick_first_upgrade.run_if(in_state(GameplayState::Drafting)),
+        );
+}
+
+/// Plays a run in `app`, usually made by [`headless_app`], until the player dies
+/// or the configured duration has been simulated.
+pub fn run_simulation(app: &mut App) -> Result<SimStats, BevyError> {
+    app.finish();
+    app.cleanup();
+
+    let started = Instant::now();
+    while app.world().resource::<State<GameScreen>>().get() != &GameScreen::Gameplay {
+        if started.elapsed() > LOAD_TIMEOUT {
+            return Err("assets did not load in time for the simulation".into());
+        }
+        app.update();
+    }
+
+    let config = *app.world().resource::<SimConfig>();
+    let timestep = app.world().resource::<Time<Fixed>>().timestep();
+    let mut simulated = Duration::ZERO;
+    let mut died = false;
+    while simulated < config.duration {
+        app.update();
+        simulated += timestep;
+        died = app
+            .world_mut()
+            .query_filtered::<(), (With<Player>, With<Dead>)>()
+            .iter(app.world())
+            .next()
+            .is_some();
+        if died {
+            break;
+        }
+    }
+
+    let world = app.world();
+    let score = world.resource::<Score>();
+    Ok(SimStats {
+        seed: world.resource::<GameRng>().seed(),
+        bot: config.bot,
+        survival_time: **world.resource::<TimeAlive>(),
+        died,
+        waves_reached: **world.resource::<CurrentWave>(),
+        score: score.total,
+        kills: score.kills.clone(),
+        damage_per_wave: world.resource::<DamageTaken>().0.clone(),
+    })
+}
+
+/// Screen shake is applied by most hits, so it has to exist even without a camera.
+fn spawn_shake(mut commands: Commands) {
+    commands.spawn(Shake::default());
+}
+
+fn start_simulated_run(mut next_screen: ResMut<NextState<GameScreen>>) {
+    next_screen.set(GameScreen::Gameplay);
+}
+
+/// Both bots take the first upgrade offered, the draft is already shuffled by the run's seed.
+fn pick_first_upgrade(draft: Res<Draft>, mut picked: EventWriter<UpgradePicked>) {
+    if let Some(&upgrade) = draft.choices.first() {
+        picked.write(UpgradePicked { upgrade });
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct DamageTaken(BTreeMap<usize, i32>);
+
+fn track_damage_taken(
+    mut events: EventReader<PlayerHitEvent>,
+    wave: Res<CurrentWave>,
+    mut damage_taken: ResMut<DamageTaken>,
+) {
+    for event in events.read() {
+        *damage_taken.0.entry(wave.saturating_sub(1)).or_default() += *event.damage;
+    }
+}
+
+#[derive(Resource, Debug)]
+struct SimBotState {
+    rng: StdRng,
+    frame: ReplayFrame,
+    decision: Timer,
+}
+
+impl Default for SimBotState {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(0),
+            frame: ReplayFrame {
+                movement: [0.0; 2],
+                aim: [0.0; 2],
+                shoot: ButtonState::Released,
+                dash: ButtonState::Released,
+                recall: ButtonState::Released,
+                nova: ButtonState::Released,
+            },
+            decision: Timer::from_seconds(RANDOM_DECISION_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+/// The bot gets its own generator so that its choices don't shift the game's streams.
+fn reset_sim_bot(mut bot: ResMut<SimBotState>, rng: Res<GameRng>) {
+    *bot = SimBotState {
+        rng: StdRng::seed_from_u64(rng.seed()),
+        ..default()
+    };
+}
+
+fn drive_player(
+    config: Res<SimConfig>,
+    mut bot: ResMut<SimBotState>,
+    time: Res<Time>,
+    player: Single<&Transform, (With<Player>, Without<Dead>)>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    mut actions: ResMut<ActionState<PlayerAction>>,
+    mut abilities: Single<&mut ActionState<PlayerAbility>, With<Player>>,
+) {
+    let position = player.translation.xy();
+    let frame = match config.bot {
+        SimBot::Scripted => scripted_frame(position, enemies.iter().map(|t| t.translation.xy())),
+        SimBot::Random => random_frame(&mut bot, time.delta()),
+    };
+    bot.frame = frame;
+    frame.apply(&mut actions, &mut abilities);
+}
+
+fn scripted_frame(position: Vec2, enemies: impl Iterator<Item = Vec2>) -> ReplayFrame {
+    let mut flee = Vec2::ZERO;
+    let mut closest: Option<Vec2> = None;
+    for enemy in enemies {
+        let offset = position - enemy;
+        let distance = offset.length();
+        if distance < SCRIPTED_FLEE_RANGE {
+            flee += offset.normalize_or_zero() * (SCRIPTED_FLEE_RANGE - distance);
+        }
+        if closest.is_none_or(|closest| distance < position.distance(closest)) {
+            closest = Some(enemy);
+        }
+    }
+    // circling keeps the bot moving, so it can always dash
+    let circle = position.perp().normalize_or(Vec2::X);
+    let movement = (flee.normalize_or_zero() * 2.0 + circle).normalize_or_zero();
+    let dash = closest.is_some_and(|closest| position.distance(closest) < SCRIPTED_DASH_RANGE);
+    ReplayFrame {
+        movement: movement.to_array(),
+        aim: closest
+            .map_or(Vec2::X, |closest| closest - position)
+            .to_array(),
+        shoot: if closest.is_some() {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        },
+        dash: if dash {
+            ButtonState::JustPressed
+        } else {
+            ButtonState::Released
+        },
+        recall: ButtonState::Released,
+        nova: ButtonState::Released,
+    }
+}
+
+fn random_frame(bot: &mut SimBotState, delta: Duration) -> ReplayFrame {
+    bot.decision.tick(delta);
+    let mut frame = bot.frame;
+    // a dash only lasts for the tick it was decided on
+    frame.dash = ButtonState::Released;
+    if !bot.decision.just_finished() {
+        return frame;
+    }
+    let rng = &mut bot.rng;
+    let movement = Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU));
+    frame.movement = (movement * rng.random_range(0.0..=1.0)).to_array();
+    frame.aim = Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU)).to_array();
+    frame.shoot = if rng.random_bool(0.8) {
+        ButtonState::Pressed
+    } else {
+        ButtonState::Released
+    };
+    if rng.random_bool(0.1) {
+        frame.dash = ButtonState::JustPressed;
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulate(bot: SimBot, seconds: u64) -> SimStats {
+        let config = SimConfig {
+            duration: Duration::from_secs(seconds),
+            bot,
+            seed: Some(7),
+        };
+        run_simulation(&mut headless_app(config)).expect("simulation failed")
+    }
+
+    #[test]
+    fn simulation_plays_a_run() {
+        let stats = simulate(SimBot::Scripted, 20);
+        assert_eq!(stats.seed, 7);
+        assert!(stats.survival_time > Duration::ZERO);
+        assert!(stats.survival_time <= Duration::from_secs(20));
+        assert!(stats.died || stats.survival_time >= Duration::from_secs(19));
+    }
+
+    #[test]
+    fn simulation_is_deterministic_for_a_seed() {
+        assert_eq!(simulate(SimBot::Random, 15), simulate(SimBot::Random, 15));
+    }
+
+    #[test]
+    fn recorded_runs_play_back_without_desyncing() {
+        let config = SimConfig {
+            duration: Duration::from_secs(10),
+            bot: SimBot::Random,
+            seed: Some(7),
+        };
+        let mut recording = headless_app(config);
+        recording.insert_resource(ReplaySession::recording(None));
+        let recorded = run_simulation(&mut recording).expect("recording failed");
+        let replay = Replay {
+            seed: recorded.seed,
+            ..recording.world().resource::<ReplaySession>().replay.clone()
+        };
+        assert!(!replay.checksums.is_empty());
+
+        let mut playback = headless_app(config);
+        playback.insert_resource(ReplaySession::playing(replay));
+        let played = run_simulation(&mut playback).expect("playback failed");
+
+        assert!(!playback.world().resource::<ReplaySession>().desynced());
+        assert_eq!(played, recorded);
+    }
+}
@@ -0,0 +1,378 @@
+use std::time::Duration;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::characters::bullet::patterns::prelude::*;
+use crate::characters::enemies::prelude::*;
+use crate::screens::GameScreen;
+
+pub mod prelude {
+    pub use super::waves_plugin;
+    pub use super::{
+        BossParams, CoffinParams, EndlessParams, HandParams, Wave, WaveAssets, WaveSet,
+        WaveTrigger, WaveTriggerContext,
+    };
+}
+
+pub fn waves_plugin(app: &mut App) {
+    app.init_asset::<WaveSet>()
+        .init_asset_loader::<WaveSetLoader>()
+        .configure_loading_state(
+            LoadingStateConfig::new(GameScreen::SplashFirst).load_collection::<WaveAssets>(),
+        )
+        .add_systems(Update, log_wave_set_reloads);
+}
+
+#[derive(Resource, AssetCollection, Debug)]
+pub struct WaveAssets {
+    #[asset(path = "waves/default.waves.ron")]
+    pub waves: Handle<WaveSet>,
+}
+
+/// The scripted list of waves for a run, loaded from a `.waves.ron` file.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WaveSet {
+    pub waves: Vec<Wave>,
+    /// How waves are generated once the scripted ones run out.
+    #[serde(default)]
+    pub endless: EndlessParams,
+    /// Fixed seed for the run's [`GameRng`](crate::rng::GameRng), a random one is
+    /// picked per run when missing.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    /// Time since the start of the run at which this wave spawns.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub timestamp: Duration,
+    #[serde(default)]
+    pub ghost_count: usize,
+    #[serde(default)]
+    pub coffin_count: usize,
+    #[serde(default)]
+    pub hand_count: usize,
+    #[serde(default)]
+    pub coffin: CoffinParams,
+    #[serde(default)]
+    pub hand: HandParams,
+    /// A boss that spawns along with the rest of the wave.
+    #[serde(default)]
+    pub boss: Option<BossParams>,
+    /// Condition that has to hold before the wave after this one spawns.
+    #[serde(default)]
+    pub advance: WaveTrigger,
+}
+
+/// When a wave lets the next one spawn, e.g. `advance: Any([Time, Cleared])`.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub enum WaveTrigger {
+    /// The next wave's `timestamp` has been reached.
+    #[default]
+    Time,
+    /// Every enemy spawned by this wave is dead, including the ghosts its coffins summoned.
+    Cleared,
+    /// Fewer than this many enemies are alive.
+    AliveBelow(usize),
+    All(Vec<WaveTrigger>),
+    Any(Vec<WaveTrigger>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WaveTriggerContext {
+    pub time_reached: bool,
+    /// Enemies of the current wave that are still alive.
+    pub remaining: usize,
+    /// All enemies that are still alive.
+    pub alive: usize,
+}
+
+impl WaveTrigger {
+    pub fn is_met(&self, context: &WaveTriggerContext) -> bool {
+        match self {
+            WaveTrigger::Time => context.time_reached,
+            WaveTrigger::Cleared => context.remaining == 0,
+            WaveTrigger::AliveBelow(count) => context.alive < *count,
+            WaveTrigger::All(triggers) => triggers.iter().all(|trigger| trigger.is_met(context)),
+            WaveTrigger::Any(triggers) => triggers.iter().any(|trigger| trigger.is_met(context)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            WaveTrigger::AliveBelow(0) => Err("`AliveBelow(0)` can never be met".into()),
+            WaveTrigger::Any(triggers) if triggers.is_empty() => {
+                Err("`Any([])` can never be met".into())
+            }
+            WaveTrigger::All(triggers) | WaveTrigger::Any(triggers) => {
+                triggers.iter().try_for_each(WaveTrigger::validate)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Wave {
+    pub fn count(&self, kind: EnemyKind) -> usize {
+        match kind {
+            EnemyKind::Ghost => self.ghost_count,
+            EnemyKind::Coffin => self.coffin_count,
+            EnemyKind::Hand => self.hand_count,
+            EnemyKind::Boss => usize::from(self.boss.is_some()),
+        }
+    }
+
+    /// `None` for bosses, a wave has at most one and it's described by `boss`.
+    pub fn count_mut(&mut self, kind: EnemyKind) -> Option<&mut usize> {
+        match kind {
+            EnemyKind::Ghost => Some(&mut self.ghost_count),
+            EnemyKind::Coffin => Some(&mut self.coffin_count),
+            EnemyKind::Hand => Some(&mut self.hand_count),
+            EnemyKind::Boss => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoffinParams {
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub initial_rate: Duration,
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub rate: Duration,
+    pub count: usize,
+    pub spacing: f32,
+}
+
+impl Default for CoffinParams {
+    fn default() -> Self {
+        Self {
+            initial_rate: Duration::from_secs_f32(1.0),
+            rate: Duration::from_secs_f32(5.0),
+            count: 5,
+            spacing: 32.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandParams {
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub shoot_rate: Duration,
+    pub finger_count: usize,
+    /// Time the hand telegraphs a volley for before shooting it.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub wind_up: Duration,
+    /// Shot instead of a fan of `finger_count` fingers.
+    pub pattern: Option<BulletPattern>,
+}
+
+impl HandParams {
+    pub fn pattern(&self) -> BulletPattern {
+        self.pattern
+            .unwrap_or_else(|| BulletPattern::fan(self.finger_count))
+    }
+}
+
+impl Default for HandParams {
+    fn default() -> Self {
+        Self {
+            shoot_rate: Duration::from_secs_f32(1.5),
+            finger_count: 5,
+            wind_up: Duration::from_secs_f32(0.5),
+            pattern: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct BossParams {
+    pub health: i32,
+    /// Fingers in each fan the boss shoots.
+    pub finger_count: usize,
+    /// Ghosts summoned at once.
+    pub summon_count: usize,
+}
+
+impl Default for BossParams {
+    fn default() -> Self {
+        Self {
+            health: 120,
+            finger_count: 9,
+            summon_count: 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndlessParams {
+    /// Time between two generated waves.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub interval: Duration,
+    /// Points to spend on the first generated wave.
+    pub initial_budget: f32,
+    /// Points added to the budget per second spent in endless mode.
+    pub budget_growth: f32,
+    pub ghost_cost: f32,
+    pub coffin_cost: f32,
+    pub hand_cost: f32,
+}
+
+impl Default for EndlessParams {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs_f32(3.0),
+            initial_budget: 180.0,
+            budget_growth: 1.5,
+            ghost_cost: 1.0,
+            coffin_cost: 4.0,
+            hand_cost: 5.0,
+        }
+    }
+}
+
+impl EndlessParams {
+    /// `None` for kinds endless waves never spawn, like bosses.
+    pub fn cost(&self, kind: EnemyKind) -> Option<f32> {
+        match kind {
+            EnemyKind::Ghost => Some(self.ghost_cost),
+            EnemyKind::Coffin => Some(self.coffin_cost),
+            EnemyKind::Hand => Some(self.hand_cost),
+            EnemyKind::Boss => None,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.interval.is_zero() {
+            return Err("`interval` must be above 0".into());
+        }
+        for kind in EnemyKind::iter() {
+            let Some(cost) = self.cost(kind) else {
+                continue;
+            };
+            if !(cost.is_finite() && cost > 0.0) {
+                return Err(format!("{kind:?} cost must be above 0, found {cost}"));
+            }
+        }
+        if !(self.initial_budget.is_finite() && self.budget_growth.is_finite()) {
+            return Err("budget values must be finite".into());
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let secs = f32::deserialize(deserializer)?;
+    Duration::try_from_secs_f32(secs).map_err(|_| {
+        D::Error::custom(format!(
+            "expected a non-negative number of seconds, found {secs}"
+        ))
+    })
+}
+
+impl WaveSet {
+    fn validate(&self) -> Result<(), WaveSetLoaderError> {
+        let invalid =
+            |index: usize, reason: String| WaveSetLoaderError::InvalidWave { index, reason };
+        let mut previous = Duration::ZERO;
+        for (index, wave) in self.waves.iter().enumerate() {
+            if wave.timestamp < previous {
+                return Err(invalid(
+                    index,
+                    format!(
+                        "timestamp {:?} is earlier than the previous wave's {:?}",
+                        wave.timestamp, previous
+                    ),
+                ));
+            }
+            previous = wave.timestamp;
+            // zero durations would make the repeating timers fire every frame
+            if wave.coffin_count > 0 && wave.coffin.rate.is_zero() {
+                return Err(invalid(index, "coffin `rate` must be above 0".into()));
+            }
+            if wave.hand_count > 0 && wave.hand.shoot_rate.is_zero() {
+                return Err(invalid(index, "hand `shoot_rate` must be above 0".into()));
+            }
+            if wave.coffin_count > 0 && !wave.coffin.spacing.is_finite() {
+                return Err(invalid(index, "coffin `spacing` must be finite".into()));
+            }
+            wave.hand
+                .pattern()
+                .validate()
+                .map_err(|reason| invalid(index, format!("hand `pattern`: {reason}")))?;
+            if wave.boss.is_some_and(|boss| boss.health <= 0) {
+                return Err(invalid(index, "boss `health` must be above 0".into()));
+            }
+            wave.advance
+                .validate()
+                .map_err(|reason| invalid(index, format!("`advance`: {reason}")))?;
+        }
+        self.endless
+            .validate()
+            .map_err(WaveSetLoaderError::InvalidEndless)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WaveSetLoaderError {
+    #[error("could not read wave set: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed wave set: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("invalid wave #{index}: {reason}")]
+    InvalidWave { index: usize, reason: String },
+    #[error("invalid endless parameters: {0}")]
+    InvalidEndless(String),
+}
+
+#[derive(Default)]
+struct WaveSetLoader;
+
+impl AssetLoader for WaveSetLoader {
+    type Asset = WaveSet;
+    type Settings = ();
+    type Error = WaveSetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<WaveSet, WaveSetLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let wave_set = ron::de::from_bytes::<WaveSet>(&bytes)?;
+        wave_set.validate()?;
+        Ok(wave_set)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+fn log_wave_set_reloads(
+    mut events: EventReader<AssetEvent<WaveSet>>,
+    wave_sets: Res<Assets<WaveSet>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } = event {
+            let count = wave_sets.get(*id).map(|set| set.waves.len());
+            tracing::info!(?id, ?count, "wave set reloaded");
+        }
+    }
+}
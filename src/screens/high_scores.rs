@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use moonshine_save::prelude::*;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::COLORS;
+use crate::screens::gameplay::score::prelude::*;
+use crate::screens::gameplay::{CurrentWave, TimeAlive};
+use crate::screens::menu::prelude::*;
+use crate::screens::{GameScreen, SAVE_PATH};
+
+pub mod prelude {
+    pub use super::{HighScoreEntry, HighScores, LatestHighScore};
+}
+
+/// Number of runs kept in the table.
+const MAX_HIGH_SCORES: usize = 10;
+
+pub fn high_scores_plugin(app: &mut App) {
+    app.register_type::<HighScores>()
+        .register_type::<HighScoreEntry>()
+        .register_type::<Vec<HighScoreEntry>>()
+        .init_resource::<LatestHighScore>()
+        // the save is loaded on entering `SplashFirst`, older saves come without a table
+        .add_systems(OnExit(GameScreen::SplashFirst), ensure_high_scores)
+        .add_systems(OnEnter(GameScreen::AfterDeath), record_high_score)
+        .add_systems(OnEnter(GameScreen::HighScores), spawn_high_scores_menu)
+        .add_systems(OnExit(GameScreen::HighScores), despawn_high_scores_menu)
+        .add_systems(
+            Update,
+            high_scores_menu_actions.run_if(in_state(GameScreen::HighScores)),
+        );
+}
+
+#[derive(Component, Reflect, Default, Debug, Deref, DerefMut)]
+#[reflect(Component, Default)]
+#[require(Save, Unload)]
+pub struct HighScores(Vec<HighScoreEntry>);
+
+#[derive(Reflect, Default, Debug, Clone, PartialEq)]
+#[reflect(Default)]
+pub struct HighScoreEntry {
+    pub score: u64,
+    pub waves: usize,
+    pub time_alive_secs: f32,
+    /// Seconds since the unix epoch.
+    pub date: u64,
+}
+
+impl HighScoreEntry {
+    /// A line of the table, after the entry's position.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  wave {}  {}  {}",
+            self.score,
+            self.waves,
+            self.time_alive(),
+            self.date()
+        )
+    }
+
+    pub fn time_alive(&self) -> String {
+        let secs = self.time_alive_secs as u64;
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+
+    /// The date formatted as `YYYY-MM-DD`.
+    pub fn date(&self) -> String {
+        // days to civil date, from Howard Hinnant's `civil_from_days`
+        let days = (self.date / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Position of the last finished run in the [`HighScores`] table, if it made it in.
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+pub struct LatestHighScore(Option<usize>);
+
+fn ensure_high_scores(mut commands: Commands, high_scores: Query<(), With<HighScores>>) {
+    if high_scores.is_empty() {
+        tracing::info!("no high score table in save, starting a new one");
+        commands.spawn(HighScores::default());
+    }
+}
+
+fn record_high_score(
+    mut commands: Commands,
+    mut high_scores: Single<&mut HighScores>,
+    mut latest: ResMut<LatestHighScore>,
+    score: Res<Score>,
+    wave: Res<CurrentWave>,
+    time_alive: Res<TimeAlive>,
+) {
+    let entry = HighScoreEntry {
+        score: score.total,
+        waves: **wave,
+        time_alive_secs: time_alive.as_secs_f32(),
+        date: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs()),
+    };
+    let position = high_scores.partition_point(|other| other.score >= entry.score);
+    **latest = (position < MAX_HIGH_SCORES).then_some(position);
+    if latest.is_none() {
+        return;
+    }
+    tracing::info!(?entry, position, "new high score");
+    high_scores.insert(position, entry);
+    high_scores.truncate(MAX_HIGH_SCORES);
+    commands.trigger_save(SaveWorld::default_into_file(SAVE_PATH));
+}
+
+#[derive(Component)]
+struct HighScoresMenu;
+
+fn spawn_high_scores_menu(mut commands: Commands, high_scores: Single<&HighScores>) {
+    commands
+        .spawn((
+            HighScoresMenu,
+            Menu::default(),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(COLORS[5]),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("HIGH SCORES"), TextColor(COLORS[2])));
+            if high_scores.is_empty() {
+                parent.spawn((Text::new("no runs yet"), TextColor(COLORS[4])));
+            }
+            for (position, entry) in high_scores.iter().enumerate() {
+                parent.spawn((
+                    Text::new(format!("{}. {}", position + 1, entry.summary())),
+                    TextColor(COLORS[3]),
+                ));
+            }
+            parent.spawn(menu_button(0, "back"));
+        });
+}
+
+/// The back button is the only button, so any activation leaves.
+fn high_scores_menu_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut back: EventReader<MenuBack>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+) {
+    if activated.read().count() + back.read().count() > 0 {
+        next_screen.set(GameScreen::MainMenu);
+    }
+}
+
+fn despawn_high_scores_menu(mut commands: Commands, menu: Single<Entity, With<HighScoresMenu>>) {
+    commands.entity(*menu).try_despawn();
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::COLORS;
+use crate::screens::GameScreen;
+use crate::screens::menu::prelude::*;
+use crate::screens::tutorial::prelude::*;
+
+pub mod prelude {
+    pub use super::main_menu_plugin;
+}
+
+pub fn main_menu_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameScreen::MainMenu), spawn_main_menu)
+        .add_systems(OnExit(GameScreen::MainMenu), despawn_main_menu)
+        .add_systems(
+            Update,
+            main_menu_actions.run_if(in_state(GameScreen::MainMenu)),
+        );
+}
+
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum MainMenuButton {
+    Start,
+    Tutorial,
+    Options,
+    HighScores,
+    Quit,
+}
+
+fn spawn_main_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            MainMenu,
+            Menu::default(),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(COLORS[5]),
+            children![
+                (
+                    Node {
+                        margin: UiRect::bottom(Val::Px(8.0)),
+                        ..default()
+                    },
+                    Text::new("BLOOD BOOMERANG"),
+                    TextColor(COLORS[1])
+                ),
+                (menu_button(0, "start"), MainMenuButton::Start),
+                (menu_button(1, "tutorial"), MainMenuButton::Tutorial),
+                (menu_button(2, "options"), MainMenuButton::Options),
+                (menu_button(3, "high scores"), MainMenuButton::HighScores),
+            ],
+        ))
+        .with_children(|parent| {
+            // browsers don't let a page close itself
+            if cfg!(not(target_arch = "wasm32")) {
+                parent.spawn((menu_button(4, "quit"), MainMenuButton::Quit));
+            }
+        });
+}
+
+fn main_menu_actions(
+    mut events: EventReader<MenuActivated>,
+    buttons: Query<&MainMenuButton>,
+    mut tutorial: Single<&mut Tutorial>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in events.read() {
+        match buttons.get(event.button) {
+            // the tutorial is shown before the first run
+            Ok(MainMenuButton::Start) if ***tutorial == TutorialState::Done => {
+                next_screen.set(GameScreen::Gameplay)
+            }
+            Ok(MainMenuButton::Start) => next_screen.set(GameScreen::Tutorial),
+            Ok(MainMenuButton::Tutorial) => {
+                ***tutorial = TutorialState::Active;
+                next_screen.set(GameScreen::Tutorial);
+            }
+            Ok(MainMenuButton::Options) => next_screen.set(GameScreen::Options),
+            Ok(MainMenuButton::HighScores) => next_screen.set(GameScreen::HighScores),
+            Ok(MainMenuButton::Quit) => {
+                exit.write(AppExit::Success);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn despawn_main_menu(mut commands: Commands, menu: Single<Entity, With<MainMenu>>) {
+    commands.entity(*menu).try_despawn();
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::COLORS;
+use crate::screens::splash::play_menu_sound;
+
+pub mod prelude {
+    pub use super::menu_button;
+    pub use super::menu_plugin;
+    pub use super::{Menu, MenuAction, MenuActivated, MenuAdjusted, MenuBack, MenuButton};
+}
+
+pub fn menu_plugin(app: &mut App) {
+    app.add_plugins(InputManagerPlugin::<MenuAction>::default())
+        .add_event::<MenuActivated>()
+        .add_event::<MenuAdjusted>()
+        .add_event::<MenuBack>()
+        .add_observer(menu_focus_on_hover)
+        .add_observer(menu_activate_on_click)
+        .add_systems(
+            Update,
+            (
+                menu_navigate,
+                menu_activate,
+                menu_adjust,
+                menu_highlight_focused,
+                play_menu_sound.run_if(on_event::<MenuActivated>),
+            )
+                .chain(),
+        );
+}
+
+/// Root of a list of [`MenuButton`]s that can be navigated with the keyboard,
+/// a gamepad or the mouse.
+#[derive(Component, Debug, Default)]
+#[require(InputMap<MenuAction> = MenuAction::input_map())]
+pub struct Menu {
+    pub focused: usize,
+    /// Set once every input held while the menu opened has been released,
+    /// so the press that opened it does not also activate a button.
+    ready: bool,
+    locked: bool,
+}
+
+impl Menu {
+    /// Stops the menu from reacting to input, e.g. while another system reads raw presses.
+    /// Unlocking waits for every input to be released again.
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+        self.ready = false;
+    }
+}
+
+/// A button of the parent [`Menu`], ordered by its index.
+#[derive(Component, Debug, Clone, Copy, Deref)]
+#[require(Button)]
+pub struct MenuButton(pub usize);
+
+pub fn menu_button(index: usize, label: impl Into<String>) -> impl Bundle {
+    (MenuButton(index), Text::new(label), TextColor(COLORS[3]))
+}
+
+/// Sent when a [`MenuButton`] is clicked or confirmed while focused.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuActivated {
+    pub menu: Entity,
+    pub button: Entity,
+}
+
+/// Sent when left or right is pressed while a [`MenuButton`] is focused, `step` is `-1` or `1`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuAdjusted {
+    pub menu: Entity,
+    pub button: Entity,
+    pub step: i32,
+}
+
+/// Sent when the back action is pressed while a [`Menu`] is open.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuBack {
+    pub menu: Entity,
+}
+
+#[derive(Actionlike, Debug, Reflect, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+impl MenuAction {
+    fn input_map() -> InputMap<Self> {
+        InputMap::default()
+            .with(MenuAction::Up, KeyCode::ArrowUp)
+            .with(MenuAction::Up, KeyCode::KeyW)
+            .with(MenuAction::Up, GamepadButton::DPadUp)
+            .with(MenuAction::Down, KeyCode::ArrowDown)
+            .with(MenuAction::Down, KeyCode::KeyS)
+            .with(MenuAction::Down, GamepadButton::DPadDown)
+            .with(MenuAction::Left, KeyCode::ArrowLeft)
+            .with(MenuAction::Left, KeyCode::KeyA)
+            .with(MenuAction::Left, GamepadButton::DPadLeft)
+            .with(MenuAction::Right, KeyCode::ArrowRight)
+            .with(MenuAction::Right, KeyCode::KeyD)
+            .with(MenuAction::Right, GamepadButton::DPadRight)
+            .with(MenuAction::Confirm, KeyCode::Enter)
+            .with(MenuAction::Confirm, KeyCode::Space)
+            .with(MenuAction::Confirm, GamepadButton::South)
+            .with(MenuAction::Back, KeyCode::Escape)
+            .with(MenuAction::Back, GamepadButton::East)
+    }
+}
+
+fn menu_focus_on_hover(
+    trigger: Trigger<Pointer<Over>>,
+    mut menus: Query<&mut Menu>,
+    buttons: Query<(&MenuButton, &ChildOf)>,
+) {
+    let Ok((index, child_of)) = buttons.get(trigger.target()) else {
+        return;
+    };
+    let Ok(mut menu) = menus.get_mut(child_of.parent()) else {
+        return;
+    };
+    if !menu.locked {
+        menu.focused = **index;
+    }
+}
+
+fn menu_activate_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut menus: Query<&mut Menu>,
+    buttons: Query<(&MenuButton, &ChildOf)>,
+    mut activated: EventWriter<MenuActivated>,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+    let Ok((index, child_of)) = buttons.get(trigger.target()) else {
+        return;
+    };
+    let Ok(mut menu) = menus.get_mut(child_of.parent()) else {
+        return;
+    };
+    if menu.locked {
+        return;
+    }
+    menu.focused = **index;
+    activated.write(MenuActivated {
+        menu: child_of.parent(),
+        button: trigger.target(),
+    });
+}
+
+fn menu_navigate(
+    mut menus: Query<(Entity, &mut Menu, &ActionState<MenuAction>)>,
+    buttons: Query<&ChildOf, With<MenuButton>>,
+    mut back: EventWriter<MenuBack>,
+) {
+    for (menu_id, mut menu, actions) in menus.iter_mut() {
+        if menu.locked {
+            continue;
+        }
+        if !menu.ready {
+            menu.ready = actions.get_pressed().is_empty();
+            continue;
+        }
+        if actions.just_pressed(&MenuAction::Back) {
+            back.write(MenuBack { menu: menu_id });
+        }
+        let count = buttons
+            .iter()
+            .filter(|child_of| child_of.parent() == menu_id)
+            .count();
+        if count == 0 {
+            continue;
+        }
+        if actions.just_pressed(&MenuAction::Down) {
+            menu.focused = (menu.focused + 1) % count;
+        }
+        if actions.just_pressed(&MenuAction::Up) {
+            menu.focused = (menu.focused + count - 1) % count;
+        }
+    }
+}
+
+fn menu_activate(
+    menus: Query<(Entity, &Menu, &ActionState<MenuAction>)>,
+    buttons: Query<(Entity, &MenuButton, &ChildOf)>,
+    mut activated: EventWriter<MenuActivated>,
+) {
+    for (menu_id, menu, actions) in menus.iter() {
+        if menu.locked || !menu.ready || !actions.just_pressed(&MenuAction::Confirm) {
+            continue;
+        }
+        if let Some((button, ..)) = buttons
+            .iter()
+            .find(|(_, index, child_of)| child_of.parent() == menu_id && ***index == menu.focused)
+        {
+            activated.write(MenuActivated {
+                menu: menu_id,
+                button,
+            });
+        }
+    }
+}
+
+fn menu_adjust(
+    menus: Query<(Entity, &Menu, &ActionState<MenuAction>)>,
+    buttons: Query<(Entity, &MenuButton, &ChildOf)>,
+    mut adjusted: EventWriter<MenuAdjusted>,
+) {
+    for (menu_id, menu, actions) in menus.iter() {
+        if menu.locked || !menu.ready {
+            continue;
+        }
+        let step = i32::from(actions.just_pressed(&MenuAction::Right))
+            - i32::from(actions.just_pressed(&MenuAction::Left));
+        if step == 0 {
+            continue;
+        }
+        if let Some((button, ..)) = buttons
+            .iter()
+            .find(|(_, index, child_of)| child_of.parent() == menu_id && ***index == menu.focused)
+        {
+            adjusted.write(MenuAdjusted {
+                menu: menu_id,
+                button,
+                step,
+            });
+        }
+    }
+}
+
+fn menu_highlight_focused(
+    menus: Query<&Menu>,
+    mut buttons: Query<(&MenuButton, &ChildOf, &mut TextColor)>,
+) {
+    for (index, child_of, mut color) in buttons.iter_mut() {
+        let Ok(menu) = menus.get(child_of.parent()) else {
+            continue;
+        };
+        let target = if **index == menu.focused {
+            COLORS[1]
+        } else {
+            COLORS[3]
+        };
+        if color.0 != target {
+            color.0 = target;
+        }
+    }
+}
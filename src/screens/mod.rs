@@ -3,24 +3,40 @@ use bevy_asset_loader::prelude::*;
 
 use crate::audio::prelude::*;
 use crate::screens::after_death::prelude::*;
+use crate::screens::controls::prelude::*;
+use crate::screens::high_scores::high_scores_plugin;
+use crate::screens::main_menu::prelude::*;
+use crate::screens::menu::prelude::*;
+use crate::screens::options::prelude::*;
+use crate::screens::pause::prelude::*;
 use crate::screens::splash::prelude::*;
 use crate::screens::tutorial::tutorial_plugin;
+use crate::screens::upgrade_menu::prelude::*;
 use crate::screens::{camera_setup::camera_setup_plugin, gameplay::gameplay_plugin};
 
 mod after_death;
 mod camera_setup;
+mod controls;
 mod gameplay;
+mod high_scores;
+mod main_menu;
+mod menu;
+mod options;
+mod pause;
 mod splash;
 mod tutorial;
+mod upgrade_menu;
 
 pub mod prelude {
-    pub use super::GameScreen;
     pub use super::camera_setup::prelude::*;
+    pub use super::gameplay::sim::prelude::*;
     pub use super::screens_plugin;
+    pub use super::{GameScreen, GameplayState};
 }
 
 pub fn screens_plugin(app: &mut App) {
     app.init_state::<GameScreen>()
+        .add_sub_state::<GameplayState>()
         .add_loading_state(
             LoadingState::new(GameScreen::SplashFirst)
                 .continue_to_state(GameScreen::SplashNext)
@@ -29,8 +45,16 @@ pub fn screens_plugin(app: &mut App) {
         .add_plugins(camera_setup_plugin)
         .add_plugins(gameplay_plugin)
         .add_plugins(splash_screen_plugin)
+        .add_plugins(main_menu_plugin)
         .add_plugins(tutorial_plugin)
-        .add_plugins(after_death_plugin);
+        .add_plugins(options_plugin)
+        .add_plugins(controls_plugin)
+        .add_plugins(high_scores_plugin)
+        .add_plugins(menu_plugin)
+        .add_plugins(pause_plugin)
+        .add_plugins(upgrade_menu_plugin)
+        .add_plugins(after_death_plugin)
+        .add_systems(OnEnter(GameScreen::Restart), restart_gameplay);
 
     #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
     {
@@ -39,14 +63,42 @@ pub fn screens_plugin(app: &mut App) {
     }
 }
 
+/// File that [`moonshine_save`] writes every entity marked with `Save` into.
+pub const SAVE_PATH: &str = "save.ron";
+
 #[derive(States, Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
 pub enum GameScreen {
     #[default]
     SplashFirst,
     SplashNext,
+    /// Title menu, shown after the splash and when quitting a run.
+    MainMenu,
     Tutorial,
     Gameplay,
     AfterDeath,
+    /// Volume settings, reached from the main menu or the tutorial.
+    Options,
+    /// Rebinding screen, reached from the options.
+    Controls,
+    /// The saved table of best runs, reached from the main menu.
+    HighScores,
+    /// Passes straight back to `Gameplay`, so that restarting a run goes through
+    /// its `OnExit` and `OnEnter` schedules.
+    Restart,
+}
+
+#[derive(SubStates, Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[source(GameScreen = GameScreen::Gameplay)]
+pub enum GameplayState {
+    #[default]
+    Running,
+    Paused,
+    /// Picking an upgrade between waves, the game is frozen like when paused.
+    Drafting,
+}
+
+fn restart_gameplay(mut next: ResMut<NextState<GameScreen>>) {
+    next.set(GameScreen::Gameplay);
 }
 
 #[derive(Resource, AssetCollection, Debug)]
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use moonshine_save::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::COLORS;
+use crate::audio::prelude::*;
+use crate::screens::menu::prelude::*;
+use crate::screens::{GameScreen, SAVE_PATH};
+
+pub mod prelude {
+    pub use super::options_plugin;
+}
+
+pub fn options_plugin(app: &mut App) {
+    app.init_resource::<OptionsOrigin>()
+        .add_systems(OnEnter(GameScreen::Options), spawn_options_menu)
+        .add_systems(
+            OnExit(GameScreen::Options),
+            (despawn_options_menu, save_volume_settings),
+        )
+        .add_systems(
+            Update,
+            (
+                remember_options_origin,
+                options_menu_actions,
+                update_volume_labels,
+            )
+                .chain()
+                .run_if(in_state(GameScreen::Options)),
+        );
+}
+
+/// Volume change of a single slider step.
+const VOLUME_STEP: f64 = 0.1;
+/// Number of characters in a slider bar.
+const SLIDER_WIDTH: usize = 10;
+
+#[derive(Component)]
+struct OptionsMenu;
+
+/// Screen the options were opened from, going back returns to it.
+#[derive(Resource, Debug, Deref)]
+struct OptionsOrigin(GameScreen);
+
+impl Default for OptionsOrigin {
+    fn default() -> Self {
+        Self(GameScreen::MainMenu)
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionsButton {
+    Volume(VolumeKind),
+    Controls,
+    Back,
+}
+
+fn spawn_options_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            OptionsMenu,
+            Menu::default(),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(COLORS[5]),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("OPTIONS"), TextColor(COLORS[2])));
+            let mut index = 0;
+            for kind in VolumeKind::iter() {
+                // labels are filled in by `update_volume_labels`
+                parent.spawn((menu_button(index, ""), OptionsButton::Volume(kind)));
+                index += 1;
+            }
+            parent.spawn((menu_button(index, "controls"), OptionsButton::Controls));
+            parent.spawn((menu_button(index + 1, "back"), OptionsButton::Back));
+            parent.spawn((
+                Text::new("left/right to change volume"),
+                TextColor(COLORS[4]),
+            ));
+        });
+}
+
+fn step_volume(settings: &mut VolumeSettings, kind: VolumeKind, steps: i32) {
+    let value = settings.get(kind) + VOLUME_STEP * f64::from(steps);
+    // keep the value on the step grid so repeated steps don't drift
+    settings.set(kind, (value / VOLUME_STEP).round() * VOLUME_STEP);
+}
+
+fn remember_options_origin(
+    mut transitions: EventReader<StateTransitionEvent<GameScreen>>,
+    mut origin: ResMut<OptionsOrigin>,
+) {
+    for transition in transitions.read() {
+        let Some(exited) = transition.exited else {
+            continue;
+        };
+        // coming back from the controls keeps the screen the options were opened from
+        if transition.entered == Some(GameScreen::Options) && exited != GameScreen::Controls {
+            *origin = OptionsOrigin(exited);
+        }
+    }
+}
+
+fn options_menu_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut adjusted: EventReader<MenuAdjusted>,
+    mut back: EventReader<MenuBack>,
+    buttons: Query<&OptionsButton>,
+    mut settings: Single<&mut VolumeSettings>,
+    origin: Res<OptionsOrigin>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+) {
+    if back.read().count() > 0 {
+        next_screen.set(**origin);
+    }
+    for event in adjusted.read() {
+        if let Ok(OptionsButton::Volume(kind)) = buttons.get(event.button) {
+            step_volume(&mut settings, *kind, event.step);
+        }
+    }
+    for event in activated.read() {
+        match buttons.get(event.button) {
+            // clicking a slider cycles through it, for mouse only players
+            Ok(OptionsButton::Volume(kind)) => {
+                if settings.get(*kind) >= 1.0 {
+                    settings.set(*kind, 0.0);
+                } else {
+                    step_volume(&mut settings, *kind, 1);
+                }
+            }
+            Ok(OptionsButton::Controls) => next_screen.set(GameScreen::Controls),
+            Ok(OptionsButton::Back) => next_screen.set(**origin),
+            Err(_) => {}
+        }
+    }
+}
+
+fn update_volume_labels(
+    settings: Single<&VolumeSettings>,
+    mut buttons: Query<(&OptionsButton, &mut Text)>,
+) {
+    for (button, mut text) in buttons.iter_mut() {
+        let OptionsButton::Volume(kind) = *button else {
+            continue;
+        };
+        let value = settings.get(kind);
+        let filled = ((value * SLIDER_WIDTH as f64).round() as usize).min(SLIDER_WIDTH);
+        let label = format!(
+            "{kind:<6} [{}{}] {:>3}%",
+            "#".repeat(filled),
+            "-".repeat(SLIDER_WIDTH - filled),
+            (value * 100.0).round()
+        );
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+fn despawn_options_menu(mut commands: Commands, menu: Single<Entity, With<OptionsMenu>>) {
+    commands.entity(*menu).try_despawn();
+}
+
+fn save_volume_settings(mut commands: Commands) {
+    commands.trigger_save(SaveWorld::default_into_file(SAVE_PATH));
+}
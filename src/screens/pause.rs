@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::COLORS;
+use crate::audio::prelude::*;
+use crate::bindings::prelude::*;
+use crate::characters::player::prelude::*;
+use crate::screens::menu::prelude::*;
+use crate::screens::{GameScreen, GameplayState};
+
+pub mod prelude {
+    pub use super::pause_plugin;
+    pub use super::{freeze_game, unfreeze_game};
+}
+
+pub fn pause_plugin(app: &mut App) {
+    app.add_plugins(InputManagerPlugin::<PauseAction>::default())
+        .init_resource::<ActionState<PauseAction>>()
+        .insert_resource(PauseAction::input_map(&InputBindings::default()))
+        .add_systems(
+            OnEnter(GameplayState::Paused),
+            (freeze_game, spawn_pause_menu),
+        )
+        .add_systems(
+            OnExit(GameplayState::Paused),
+            (unfreeze_game, despawn_pause_menu),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_pause_bindings,
+                toggle_pause,
+                pause_when_unfocused.run_if(in_state(GameplayState::Running)),
+                pause_menu_actions.run_if(in_state(GameplayState::Paused)),
+                update_pause_hint.run_if(in_state(GameplayState::Paused)),
+            ),
+        );
+}
+
+#[derive(Actionlike, Debug, Reflect, PartialEq, Eq, Clone, Copy, Hash)]
+enum PauseAction {
+    Toggle,
+}
+
+impl PauseAction {
+    fn input_map(bindings: &InputBindings) -> InputMap<Self> {
+        bindings.input_map(BindableAction::Pause, PauseAction::Toggle)
+    }
+}
+
+fn apply_pause_bindings(
+    bindings: Single<&InputBindings, Changed<InputBindings>>,
+    mut input_map: ResMut<InputMap<PauseAction>>,
+) {
+    *input_map = PauseAction::input_map(&bindings);
+}
+
+fn toggle_pause(
+    actions: Res<ActionState<PauseAction>>,
+    state: Option<Res<State<GameplayState>>>,
+    mut next: ResMut<NextState<GameplayState>>,
+) {
+    let Some(state) = state else {
+        return;
+    };
+    if !actions.just_pressed(&PauseAction::Toggle) {
+        return;
+    }
+    match state.get() {
+        GameplayState::Running => next.set(GameplayState::Paused),
+        GameplayState::Paused => next.set(GameplayState::Running),
+        // a draft has to be picked from
+        GameplayState::Drafting => {}
+    }
+}
+
+fn pause_when_unfocused(
+    mut events: EventReader<WindowFocused>,
+    mut next: ResMut<NextState<GameplayState>>,
+) {
+    if events.read().any(|event| !event.focused) {
+        next.set(GameplayState::Paused);
+    }
+}
+
+/// Stops virtual time, which holds back `FixedUpdate` and every timer ticked from [`Time`],
+/// along with the physics pipeline and the music and gameplay sounds.
+pub fn freeze_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier: Query<&mut RapierConfiguration>,
+    music: Res<AudioChannel<Music>>,
+    sfx: Res<AudioChannel<Sfx>>,
+) {
+    time.pause();
+    for mut config in rapier.iter_mut() {
+        config.physics_pipeline_active = false;
+    }
+    music.pause();
+    sfx.pause();
+}
+
+pub fn unfreeze_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier: Query<&mut RapierConfiguration>,
+    music: Res<AudioChannel<Music>>,
+    sfx: Res<AudioChannel<Sfx>>,
+) {
+    time.unpause();
+    for mut config in rapier.iter_mut() {
+        config.physics_pipeline_active = true;
+    }
+    music.resume();
+    sfx.resume();
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct PauseHint;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Title,
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands.spawn((
+        PauseMenu,
+        Menu::default(),
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        BackgroundColor(COLORS[5].with_alpha(0.8)),
+        GlobalZIndex(10),
+        children![
+            (Text::new("PAUSED"), TextColor(COLORS[2])),
+            (menu_button(0, "resume"), PauseButton::Resume),
+            (menu_button(1, "restart"), PauseButton::Restart),
+            (menu_button(2, "title"), PauseButton::Title),
+            (PauseHint, Text::default(), TextColor(COLORS[4])),
+        ],
+    ));
+}
+
+fn update_pause_hint(
+    mut hint: Single<&mut Text, With<PauseHint>>,
+    active_input: Res<State<ActiveInput>>,
+    bindings: Single<&InputBindings>,
+) {
+    let slot = match active_input.get() {
+        ActiveInput::MouseKeyboard => BindingSlot::KeyboardMouse,
+        ActiveInput::Gamepad => BindingSlot::Gamepad,
+    };
+    let text = format!(
+        "{} to resume",
+        bindings.pause.get(slot).to_string().to_uppercase()
+    );
+    if hint.0 != text {
+        hint.0 = text;
+    }
+}
+
+fn pause_menu_actions(
+    mut events: EventReader<MenuActivated>,
+    mut back: EventReader<MenuBack>,
+    buttons: Query<&PauseButton>,
+    mut next_gameplay: ResMut<NextState<GameplayState>>,
+    mut next_screen: ResMut<NextState<GameScreen>>,
+) {
+    if back.read().count() > 0 {
+        next_gameplay.set(GameplayState::Running);
+    }
+    for event in events.read() {
+        match buttons.get(event.button) {
+            Ok(PauseButton::Resume) => next_gameplay.set(GameplayState::Running),
+            Ok(PauseButton::Restart) => next_screen.set(GameScreen::Restart),
+            Ok(PauseButton::Title) => next_screen.set(GameScreen::MainMenu),
+            Err(_) => {}
+        }
+    }
+}
+
+fn despawn_pause_menu(mut commands: Commands, menu: Single<Entity, With<PauseMenu>>) {
+    commands.entity(*menu).try_despawn();
+}
@@ -3,6 +3,7 @@ use std::time::Duration;
 use bevy::prelude::*;
 
 use crate::audio::prelude::*;
+use crate::autotimer::prelude::*;
 use crate::screens::{GameScreen, MenuAssets};
 
 pub mod prelude {
@@ -23,10 +24,20 @@ pub fn splash_screen_plugin(app: &mut App) {
         .add_systems(OnEnter(GameScreen::Gameplay), play_bg_music);
 }
 
+type SplashTimer = AutoTimer<1000, TimerOnce>;
+
 #[derive(Component, Default, Debug)]
+#[require(SplashTimer)]
 struct SplashScreen;
 
-fn spawn_splash_screen(mut commands: Commands, asset_server: ResMut<AssetServer>) {
+fn spawn_splash_screen(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    existing: Query<(), With<SplashScreen>>,
+) {
+    if !existing.is_empty() {
+        return;
+    }
     let splash = asset_server.load::<Image>("splash_art.png");
 
     commands.spawn((
@@ -46,11 +57,11 @@ fn continue_to_splash_next(mut next_screen: ResMut<NextState<GameScreen>>) {
 fn splash_next_system(
     mut next_screen: ResMut<NextState<GameScreen>>,
     time: Res<Time>,
-    mut elapsed: Local<Duration>,
+    mut timer: Single<&mut SplashTimer, With<SplashScreen>>,
 ) {
-    *elapsed += time.delta();
-    if elapsed.as_secs_f32() > 1.0 {
-        next_screen.set(GameScreen::Tutorial);
+    timer.tick(time.delta());
+    if timer.just_finished() {
+        next_screen.set(GameScreen::MainMenu);
     }
 }
 
@@ -58,17 +69,18 @@ fn despawn_splash_screen(mut commands: Commands, splash: Single<Entity, With<Spl
     commands.entity(*splash).try_despawn();
 }
 
-pub fn play_menu_sound(audio: Res<Audio>, volume: Res<VolumeSettings>, assets: Res<MenuAssets>) {
-    audio
-        .play(assets.menu_sound.clone())
-        .with_volume(volume.calc_sfx(1.0));
+pub fn play_menu_sound(ui: Res<AudioChannel<Ui>>, assets: Res<MenuAssets>) {
+    ui.play(assets.menu_sound.clone());
 }
 
 #[derive(Component)]
 pub struct BgMusic;
 
+/// Volume of the background music, the [`Music`] channel applies the [`VolumeSettings`].
+const BG_MUSIC_VOLUME: f64 = 0.2;
+
 pub fn play_bg_music(
-    audio: Res<Audio>,
+    music_channel: Res<AudioChannel<Music>>,
     assets: Res<MenuAssets>,
     music: Option<Single<&BgMusic>>,
     mut commands: Commands,
@@ -77,9 +89,9 @@ pub fn play_bg_music(
         return;
     }
     commands.spawn(BgMusic);
-    audio
+    music_channel
         .play(assets.background_music.clone())
         .looped()
         .fade_in(AudioTween::linear(Duration::from_secs_f32(2.0)))
-        .with_volume(0.2);
+        .with_volume(BG_MUSIC_VOLUME);
 }
@@ -5,27 +5,36 @@ use tracing::instrument;
 
 use crate::{
     COLORS,
+    bindings::prelude::*,
     characters::player::{despawn_player, spawn_player},
-    screens::{GameScreen, prelude::InGameCamera},
+    screens::{GameScreen, SAVE_PATH, prelude::InGameCamera},
 };
 
-pub mod prelude {}
+pub mod prelude {
+    pub use super::{Tutorial, TutorialState};
+}
 
 pub fn tutorial_plugin(app: &mut App) {
     app.register_type::<Tutorial>()
         .register_type::<TutorialState>()
         .add_observer(save_on_default_event)
         .add_observer(load_on_default_event)
-        .add_plugins(InputManagerPlugin::<TutorialScreenConfirm>::default())
+        .add_plugins(InputManagerPlugin::<TutorialScreenAction>::default())
         .add_systems(OnEnter(GameScreen::SplashFirst), load_save)
-        .add_systems(OnExit(GameScreen::Tutorial), finish_tutorial)
+        // leaving for the options screen comes back to the tutorial
+        .add_systems(
+            OnTransition {
+                exited: GameScreen::Tutorial,
+                entered: GameScreen::Gameplay,
+            },
+            finish_tutorial,
+        )
         .add_systems(Startup, |mut commands: Commands| {
             commands.spawn(Tutorial(TutorialState::Active));
         })
         .add_systems(
             Update,
-            (advance_state_if_tutorial_done, check_tutorial_confirm)
-                .run_if(in_state(GameScreen::Tutorial)),
+            check_tutorial_confirm.run_if(in_state(GameScreen::Tutorial)),
         )
         .add_systems(
             OnEnter(GameScreen::Tutorial),
@@ -37,47 +46,46 @@ pub fn tutorial_plugin(app: &mut App) {
         );
 }
 
+/// Whether the tutorial was played through, the main menu's start skips it once it's done.
 #[derive(Component, Reflect, Deref, DerefMut)]
 #[require(Save, Unload)]
-struct Tutorial(TutorialState);
+pub struct Tutorial(TutorialState);
 
 #[derive(Default, Reflect, PartialEq, Eq)]
-enum TutorialState {
+pub enum TutorialState {
     #[default]
     Active,
     Done,
 }
 
 fn load_save(mut commands: Commands) {
-    commands.trigger_load(LoadWorld::default_from_file("save.ron"));
+    commands.trigger_load(LoadWorld::default_from_file(SAVE_PATH));
 }
 
 fn finish_tutorial(mut commands: Commands, mut tutorial: Single<&mut Tutorial>) {
     **tutorial = Tutorial(TutorialState::Done);
-    commands.trigger_save(SaveWorld::default_into_file("save.ron"));
+    commands.trigger_save(SaveWorld::default_into_file(SAVE_PATH));
 }
 
-fn advance_state_if_tutorial_done(
-    tutorial: Single<(Entity, &Tutorial)>,
-    mut next: ResMut<NextState<GameScreen>>,
-) {
-    let (entity, tutorial) = tutorial.into_inner();
-    if tutorial.0 == TutorialState::Done {
-        next.set(GameScreen::Gameplay);
-    }
+#[derive(Component, Default)]
+struct TutorialScene {
+    /// Set once confirm is released, coming back from the options screen
+    /// with confirm still held should not start the game.
+    ready: bool,
 }
 
-#[derive(Component)]
-struct TutorialScene;
-
-fn spawn_tutorial_text(mut commands: Commands, camera: Single<Entity, With<InGameCamera>>) {
+fn spawn_tutorial_text(
+    mut commands: Commands,
+    camera: Single<Entity, With<InGameCamera>>,
+    bindings: Single<&InputBindings>,
+) {
     let font = TextFont {
         font_size: 10.0,
         ..default()
     };
     commands.spawn((
-        TutorialScene,
-        TutorialScreenConfirm::input_map(),
+        TutorialScene::default(),
+        TutorialScreenAction::input_map(&bindings),
         UiTargetCamera(*camera),
         font.clone(),
         Node {
@@ -101,12 +109,34 @@ fn spawn_tutorial_text(mut commands: Commands, camera: Single<Entity, With<InGam
                 font.clone(),
             ),
             (
-                Text::new("left click OR right bumper to shoot"),
+                Text::new(format!(
+                    "{} to shoot",
+                    bindings.describe(BindableAction::Shoot)
+                )),
+                TextColor(COLORS[3]),
+                font.clone(),
+            ),
+            (
+                Text::new(format!(
+                    "{} to dash",
+                    bindings.describe(BindableAction::Dash)
+                )),
+                TextColor(COLORS[3]),
+                font.clone(),
+            ),
+            (
+                Text::new(format!(
+                    "{} to recall",
+                    bindings.describe(BindableAction::Recall)
+                )),
                 TextColor(COLORS[3]),
                 font.clone(),
             ),
             (
-                Text::new("space OR left trigger to dash"),
+                Text::new(format!(
+                    "{} to bleed",
+                    bindings.describe(BindableAction::Nova)
+                )),
                 TextColor(COLORS[3]),
                 font.clone(),
             ),
@@ -120,34 +150,53 @@ fn spawn_tutorial_text(mut commands: Commands, camera: Single<Entity, With<InGam
                 font.clone(),
             ),
             (
-                Text::new("press ENTER or R3 to PLAY"),
+                Text::new(format!(
+                    "press {} to PLAY",
+                    bindings.describe(BindableAction::Confirm).to_uppercase()
+                )),
                 TextColor(COLORS[2]),
                 font.clone(),
+            ),
+            (
+                Text::new("press TAB OR SELECT for OPTIONS"),
+                TextColor(COLORS[4]),
+                font.clone(),
             )
         ],
     ));
 }
 
-#[derive(Component, Actionlike, Reflect, Clone, PartialEq, Eq, Hash, Debug)]
-struct TutorialScreenConfirm;
+#[derive(Component, Actionlike, Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum TutorialScreenAction {
+    Confirm,
+    Options,
+}
 
-impl TutorialScreenConfirm {
-    fn input_map() -> InputMap<TutorialScreenConfirm> {
-        InputMap::default()
-            .with(TutorialScreenConfirm, KeyCode::Enter)
-            .with(TutorialScreenConfirm, GamepadButton::C)
-            .with(TutorialScreenConfirm, GamepadButton::RightThumb)
+impl TutorialScreenAction {
+    fn input_map(bindings: &InputBindings) -> InputMap<TutorialScreenAction> {
+        // the way to the options screen stays fixed so controls can't be rebound away
+        bindings
+            .input_map(BindableAction::Confirm, TutorialScreenAction::Confirm)
+            .with(TutorialScreenAction::Options, KeyCode::Tab)
+            .with(TutorialScreenAction::Options, GamepadButton::Select)
     }
 }
 
 #[instrument(skip_all)]
 fn check_tutorial_confirm(
-    query: Single<&ActionState<TutorialScreenConfirm>, With<TutorialScene>>,
-    mut tutorial: Single<&mut Tutorial>,
+    query: Single<(&ActionState<TutorialScreenAction>, &mut TutorialScene)>,
+    mut next: ResMut<NextState<GameScreen>>,
 ) {
-    if query.pressed(&TutorialScreenConfirm) {
-        ***tutorial = TutorialState::Done;
+    let (actions, mut scene) = query.into_inner();
+    if !scene.ready {
+        scene.ready = !actions.pressed(&TutorialScreenAction::Confirm);
+        return;
+    }
+    if actions.pressed(&TutorialScreenAction::Confirm) {
         tracing::info!("tutorial confirm!");
+        next.set(GameScreen::Gameplay);
+    } else if actions.just_pressed(&TutorialScreenAction::Options) {
+        next.set(GameScreen::Options);
     }
 }
 
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::COLORS;
+use crate::characters::player::upgrades::prelude::*;
+use crate::screens::GameplayState;
+use crate::screens::gameplay::draft::prelude::*;
+use crate::screens::menu::prelude::*;
+
+pub mod prelude {
+    pub use super::upgrade_menu_plugin;
+}
+
+pub fn upgrade_menu_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameplayState::Drafting), spawn_upgrade_menu)
+        .add_systems(OnExit(GameplayState::Drafting), despawn_upgrade_menu)
+        .add_systems(
+            Update,
+            upgrade_menu_actions.run_if(in_state(GameplayState::Drafting)),
+        );
+}
+
+#[derive(Component)]
+struct UpgradeMenu;
+
+#[derive(Component, Debug, Clone, Copy)]
+struct UpgradeButton(Upgrade);
+
+fn spawn_upgrade_menu(mut commands: Commands, draft: Res<Draft>) {
+    commands
+        .spawn((
+            UpgradeMenu,
+            Menu::default(),
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(COLORS[5].with_alpha(0.8)),
+            GlobalZIndex(10),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("PICK AN UPGRADE"), TextColor(COLORS[2])));
+            for (index, upgrade) in draft.choices.iter().enumerate() {
+                parent.spawn((
+                    menu_button(
+                        index,
+                        format!("{}: {}", upgrade.name(), upgrade.description()),
+                    ),
+                    UpgradeButton(*upgrade),
+                ));
+            }
+        });
+}
+
+fn upgrade_menu_actions(
+    mut events: EventReader<MenuActivated>,
+    buttons: Query<&UpgradeButton>,
+    mut picked: EventWriter<UpgradePicked>,
+) {
+    for event in events.read() {
+        if let Ok(UpgradeButton(upgrade)) = buttons.get(event.button) {
+            picked.write(UpgradePicked { upgrade: *upgrade });
+        }
+    }
+}
+
+fn despawn_upgrade_menu(mut commands: Commands, menu: Single<Entity, With<UpgradeMenu>>) {
+    commands.entity(*menu).try_despawn();
+}